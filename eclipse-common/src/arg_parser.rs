@@ -21,7 +21,16 @@
 //! methods need to be passed to the `take_*` methods on the `ParseResult`.
 //! 
 //! Arguments that were not parsed are aggregated in a `Vec` and can be retrieved
-//! by calling `get_remainder` on the `ParseResult`.
+//! by calling `get_remainder` on the `ParseResult`. Problems found while parsing,
+//! e.g. options missing their value, do not abort parsing, but are reported as
+//! `ParseDiagnostic`s via `ParseResult::diagnostics`.
+//!
+//! Values of options can either be passed as the following argument (`-name foo`)
+//! or attached to the option name with `=` (`--launcher.timeout=30`).
+//!
+//! Options registered via `Parser::add_typed_option` are converted to a type
+//! implementing `OptionValue` when taken from the `ParseResult`. Invalid values
+//! result in an `OptionValueError` naming the option.
 //!
 //! Example:
 //! ```
@@ -52,6 +61,10 @@
 //! ```
 
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Eq, PartialEq, Hash, Debug, Clone, Copy)]
 pub struct OptionId(usize);
@@ -59,13 +72,77 @@ pub struct OptionId(usize);
 #[derive(Eq, PartialEq, Hash, Debug, Clone, Copy)]
 pub struct OptionalOptionId(usize);
 
+#[derive(Eq, PartialEq, Hash, Debug, Clone, Copy)]
+pub struct RepeatedOptionId(usize);
+
+/// Identifies an option whose value is converted to `T`. The option name
+/// is kept for error messages.
+#[derive(Debug)]
+pub struct TypedOptionId<T> {
+    id: OptionId,
+    name: &'static str,
+    value_type: PhantomData<fn() -> T>,
+}
+
+// not derived, since derive would require `T: Clone`
+impl<T> Clone for TypedOptionId<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TypedOptionId<T> {}
+
+/// Identifies a repeated option whose values are converted to `T`. The option name
+/// is kept for error messages.
+#[derive(Debug)]
+pub struct TypedRepeatedOptionId<T> {
+    id: RepeatedOptionId,
+    name: &'static str,
+    value_type: PhantomData<fn() -> T>,
+}
+
+// not derived, since derive would require `T: Clone`
+impl<T> Clone for TypedRepeatedOptionId<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TypedRepeatedOptionId<T> {}
+
 #[derive(Eq, PartialEq, Hash, Debug, Clone, Copy)]
 pub struct FlagId(usize);
 
 #[derive(Eq, PartialEq, Hash, Debug, Clone, Copy)]
 pub struct ListId(usize);
 
+impl OptionId {
+    /// Returns the id for taking the value of this option converted to `T`.
+    /// The `name` of the option is used in error messages.
+    pub fn typed<T: OptionValue>(self, name: &'static str) -> TypedOptionId<T> {
+        TypedOptionId {
+            id: self,
+            name,
+            value_type: PhantomData,
+        }
+    }
+}
+
+impl RepeatedOptionId {
+    /// Returns the id for taking the values of this repeated option converted to `T`.
+    /// The `name` of the option is used in error messages.
+    pub fn typed<T: OptionValue>(self, name: &'static str) -> TypedRepeatedOptionId<T> {
+        TypedRepeatedOptionId {
+            id: self,
+            name,
+            value_type: PhantomData,
+        }
+    }
+}
+
 /// This type represents two ways of parsing a list parameter.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
 pub enum ListParseStyle {
     /// This parsing variant aggregates all remaining parameters
     /// into the resulting list of values for a given argument.
@@ -105,14 +182,184 @@ impl Default for OptionalParam {
     }
 }
 
+/// Categories of problems detected while parsing arguments.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum DiagnosticKind {
+    /// An option is not followed by a value, or the following argument
+    /// is the name of another registered parameter.
+    MissingValue,
+
+    /// A parameter is present more than once. The last occurrence wins.
+    DuplicateOption,
+
+    /// An argument starts with a prefix registered via `Parser::check_unknown_prefix`,
+    /// but is not a known parameter name.
+    UnknownOption,
+
+    /// A list parameter is followed by further arguments that are not part of the list.
+    ListNotLast,
+}
+
+/// A problem detected while parsing arguments. Holds the index of the
+/// offending argument in the parsed arguments and the argument itself.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct ParseDiagnostic {
+    pub index: usize,
+    pub token: String,
+    pub kind: DiagnosticKind,
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use DiagnosticKind::*;
+        let problem = match self.kind {
+            MissingValue => "is missing a value",
+            DuplicateOption => "is specified more than once",
+            UnknownOption => "is not a known option",
+            ListNotLast => "is followed by arguments that are not part of its list",
+        };
+        write!(f, "Argument {} '{}' {}", self.index, self.token, problem)
+    }
+}
+
+/// Describes a parsing rule, for adding rules from a declarative
+/// description of parameters via `Parser::add_rule`.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum RuleKind {
+    Option,
+    OptionalOption,
+    RepeatedOption,
+    Flag,
+    List(ListParseStyle),
+}
+
+/// The id returned by `Parser::add_rule`, holding the id of the added rule.
+#[derive(Eq, PartialEq, Hash, Debug, Clone, Copy)]
+pub enum RuleId {
+    Option(OptionId),
+    OptionalOption(OptionalOptionId),
+    RepeatedOption(RepeatedOptionId),
+    Flag(FlagId),
+    List(ListId),
+}
+
+/// Types an option value can be converted to, see `Parser::add_typed_option`.
+/// Enumerations can implement this trait via `parse_choice`.
+pub trait OptionValue: Sized {
+    /// Converts `value` into `Self`. The error describes the expected value,
+    /// e.g. "expected a non-negative integer".
+    fn parse_value(value: &str) -> Result<Self, String>;
+}
+
+macro_rules! impl_option_value {
+    ($($value_type:ty => $expected:expr),* $(,)?) => {
+        $(impl OptionValue for $value_type {
+            fn parse_value(value: &str) -> Result<Self, String> {
+                value.parse().map_err(|_| format!("expected {}", $expected))
+            }
+        })*
+    };
+}
+
+impl_option_value! {
+    u16 => "a non-negative integer",
+    u32 => "a non-negative integer",
+    u64 => "a non-negative integer",
+    usize => "a non-negative integer",
+    i32 => "an integer",
+    i64 => "an integer",
+    bool => "true or false",
+}
+
+impl OptionValue for String {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        Ok(value.to_string())
+    }
+}
+
+impl OptionValue for PathBuf {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        if value.is_empty() {
+            return Err("expected a path".to_string());
+        }
+        Ok(PathBuf::from(value))
+    }
+}
+
+/// Durations are given in seconds, unless the number is followed
+/// by one of the units `ms`, `s` or `m`.
+impl OptionValue for Duration {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        let (number, unit_millis) = if let Some(number) = value.strip_suffix("ms") {
+            (number, 1)
+        } else if let Some(number) = value.strip_suffix('s') {
+            (number, 1000)
+        } else if let Some(number) = value.strip_suffix('m') {
+            (number, 60_000)
+        } else {
+            (value, 1000)
+        };
+        number
+            .parse::<u64>()
+            .ok()
+            .and_then(|number| number.checked_mul(unit_millis))
+            .map(Duration::from_millis)
+            .ok_or_else(|| "expected a duration in seconds, e.g. 30 or 500ms".to_string())
+    }
+}
+
+/// Converts `value` to the element of `choices` with the matching name.
+/// Intended for implementing `OptionValue` for enumerations.
+pub fn parse_choice<T: Copy>(value: &str, choices: &[(&str, T)]) -> Result<T, String> {
+    match choices.iter().find(|(name, _)| *name == value) {
+        Some((_, choice)) => Ok(*choice),
+        None => {
+            let names: Vec<&str> = choices.iter().map(|(name, _)| *name).collect();
+            Err(format!("expected one of {}", names.join(", ")))
+        }
+    }
+}
+
+/// The value of a typed option cannot be converted to the option's type.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct OptionValueError {
+    pub option: &'static str,
+    pub value: String,
+    pub message: String,
+}
+
+impl fmt::Display for OptionValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid value '{}' for option {}: {}",
+            self.value, self.option, self.message
+        )
+    }
+}
+
+impl std::error::Error for OptionValueError {}
+
 enum ArgumentType {
     Option(OptionId),
     OptionalOption(OptionalOptionId),
+    RepeatedOption(RepeatedOptionId),
     Flag(FlagId),
     List(ListId, ListParseStyle),
 }
 
-/// A simple parser for command line arguments. Four types of parsing
+impl ArgumentType {
+    /// Returns `true` for argument types accepting a `name=value` argument
+    fn takes_value(&self) -> bool {
+        use ArgumentType::*;
+        match self {
+            Option(_) | OptionalOption(_) | RepeatedOption(_) => true,
+            Flag(_) | List(..) => false,
+        }
+    }
+}
+
+/// A simple parser for command line arguments. Different types of parsing
 /// rules can be registered on the parser, before starting the parsing
 /// using the `parse` function:
 /// * Flags: call `add_flag` for parameters that do not have a following
@@ -120,10 +367,15 @@ enum ArgumentType {
 /// * Options: call `add_option` for parameters that have exactly one folloing
 ///   value after the parameter. The parse result will be an `Option<String>`
 ///   wich will hold a value if the parameter was found.
-/// * Optional option
+/// * Optional option: call `add_optional_option` for parameters that may be followed
+///   by a value. The parse result will be an `OptionalParam`.
+/// * Repeated options: call `add_repeated_option` for parameters followed by a value that
+///   may be specified multiple times. The parse result will be a `Vec<String>` of all values.
+/// * Lists: call `add_list` for parameters followed by multiple values.
 pub struct Parser {
     args_by_name: HashMap<&'static str, ArgumentType>,
     next_index: usize,
+    unknown_prefix: Option<(&'static str, &'static [&'static str])>,
 }
 
 impl Parser {
@@ -131,9 +383,18 @@ impl Parser {
         Parser {
             next_index: 0,
             args_by_name: HashMap::new(),
+            unknown_prefix: None,
         }
     }
 
+    /// Makes `parse` report arguments starting with `prefix` as `DiagnosticKind::UnknownOption`,
+    /// unless they were registered via one of the `add_*` methods or are contained in `known`.
+    /// The `known` names are parameters that are handled elsewhere, so this parser does not
+    /// have to register rules for them. Such arguments are still added to the remainder.
+    pub fn check_unknown_prefix(&mut self, prefix: &'static str, known: &'static [&'static str]) {
+        self.unknown_prefix = Some((prefix, known));
+    }
+
     /// Adds the parsing rule for a parameter specified via `name` that is followed by a value.
     /// After calling `parse`, the value can be obtained via the `ParseResult::take_option`
     /// method by providing the `OptionId` returned by this method.
@@ -155,6 +416,48 @@ impl Parser {
         result
     }
 
+    /// Adds the parsing rule for a parameter specified via `name` that is followed by a value
+    /// of type `T`. After calling `parse`, the converted value can be obtained via
+    /// `ParseResult::take_typed_option` by providing the returned `TypedOptionId`.
+    pub fn add_typed_option<T: OptionValue>(&mut self, name: &'static str) -> TypedOptionId<T> {
+        self.add_option(name).typed(name)
+    }
+
+    /// Adds the parsing rule of the given `kind` for a parameter specified via `name`.
+    /// The returned `RuleId` holds the id of the rule, as returned by the `add_*` method
+    /// for this kind of rule.
+    pub fn add_rule(&mut self, name: &'static str, kind: RuleKind) -> RuleId {
+        match kind {
+            RuleKind::Option => RuleId::Option(self.add_option(name)),
+            RuleKind::OptionalOption => RuleId::OptionalOption(self.add_optional_option(name)),
+            RuleKind::RepeatedOption => RuleId::RepeatedOption(self.add_repeated_option(name)),
+            RuleKind::Flag => RuleId::Flag(self.add_flag(name)),
+            RuleKind::List(style) => RuleId::List(self.add_list(name, style)),
+        }
+    }
+
+    /// Adds the parsing rule for a parameter specified via `name` that is followed by a value and
+    /// may be present multiple times. After calling `parse`, the values of all occurrences can be
+    /// obtained via `ParseResult::take_repeated_option` by providing the returned `RepeatedOptionId`.
+    pub fn add_repeated_option(&mut self, name: &'static str) -> RepeatedOptionId {
+        let result = RepeatedOptionId(self.next_index);
+        self.args_by_name
+            .insert(name, ArgumentType::RepeatedOption(result));
+        self.next_index += 1;
+        result
+    }
+
+    /// Adds the parsing rule for a parameter specified via `name` that is followed by a value
+    /// of type `T` and may be present multiple times. After calling `parse`, the converted values
+    /// can be obtained via `ParseResult::take_typed_repeated_option` by providing the returned
+    /// `TypedRepeatedOptionId`.
+    pub fn add_typed_repeated_option<T: OptionValue>(
+        &mut self,
+        name: &'static str,
+    ) -> TypedRepeatedOptionId<T> {
+        self.add_repeated_option(name).typed(name)
+    }
+
     /// Adds the parsing rule for a parameter specified via `name` that is _not_ followed by a value.
     /// After calling `parse`, the method `ParseResult::take_flag` returns `true` if the flag was present.
     /// The returned `FlagId` has to be passed to `take_flag` for this matter.
@@ -175,9 +478,7 @@ impl Parser {
     ///   "-" prefix are added to the list. Different parameters may follow.
     ///
     /// The returned `ListId` has to be passed to `take_list` for retrieving the
-    /// parsed elements.
-    /// There can also just be one list rule, calling this method again will overwrite the
-    /// previously added list rule.
+    /// parsed elements. Multiple list rules can be added.
     pub fn add_list(&mut self, name: &'static str, style: ListParseStyle) -> ListId {
         let result = ListId(self.next_index);
         self.args_by_name
@@ -193,69 +494,91 @@ impl Parser {
         let mut found_flags = HashSet::<FlagId>::new();
         let mut found_options = HashMap::<OptionId, String>::new();
         let mut found_optionaloptions = HashMap::<OptionalOptionId, OptionalParam>::new();
-        let mut found_list: Option<(ListId, Vec<String>)> = None;
-        let mut iter = args.into_iter().peekable();
+        let mut found_repeated = HashMap::<RepeatedOptionId, Vec<String>>::new();
+        let mut found_lists = HashMap::<ListId, Vec<String>>::new();
+        let mut found_names = HashSet::<&str>::new();
+        let mut diagnostics = Vec::<ParseDiagnostic>::new();
+        let mut iter = args.into_iter().enumerate().peekable();
         let mut remaining_args = Vec::new(); 
-        'parse_loop: while let Some(ref arg_name) = iter.next() {
-            let key: &str = arg_name;
+        'parse_loop: while let Some((index, arg_name)) = iter.next() {
+            let diagnostic = |kind| ParseDiagnostic {
+                index,
+                token: arg_name.to_owned(),
+                kind,
+            };
             // is this an argument to parse?
-            let arg = match self.args_by_name.get(key) {
-                Some(arg) => arg,
+            let (name, arg, inline_value) = match self.lookup(arg_name) {
+                Some(found) => found,
                 None => {
-                    remaining_args.push(*arg_name);
+                    if self.is_unknown(arg_name) {
+                        diagnostics.push(diagnostic(DiagnosticKind::UnknownOption));
+                    }
+                    remaining_args.push(arg_name);
                     continue;
                 },
             };
+            let is_repeatable = matches!(arg, ArgumentType::RepeatedOption(_));
+            if !found_names.insert(name) && !is_repeatable {
+                diagnostics.push(diagnostic(DiagnosticKind::DuplicateOption));
+            }
+            // is the next argument a value, or the next parameter?
+            let next_is_value = match iter.peek() {
+                Some((_, next)) => inline_value.is_none() && self.lookup(next).is_none(),
+                None => false,
+            };
+            // value of an option either attached via `=`, or the next argument
+            let mut take_value = || match inline_value {
+                Some(value) => Some(value),
+                // unwrapping is safe, since peek already determined
+                // a next element exists
+                None if next_is_value => Some(iter.next().unwrap().1),
+                None => None,
+            };
             use ArgumentType::*;
             // apply parsing rule
             match arg {
                 Flag(id) => {
                     found_flags.insert(*id);
                 }
-                Option(id) => {
-                    // TODO: flatten, this nests too deep
-                    if let Some(arg_value) = iter.peek() {
-                        let key: &str = arg_value;
-                        // TODO: don't fail, just warn
-                        if !self.args_by_name.contains_key(key) {
-                            iter.next();
-                            let opt_value = key.to_owned();
-                            found_options.insert(*id, opt_value.to_owned());
-                        } else {
-                            // TODO: some kind of error!
-                        }
+                Option(id) => match take_value() {
+                    Some(opt_value) => {
+                        found_options.insert(*id, opt_value.to_owned());
                     }
-                }
-                OptionalOption(id) => {
-                    // TODO: flatten, this nests too deep
-                    if let Some(arg_value) = iter.peek() {
-                        let key: &str = arg_value;
-                        let opt_value = if self.args_by_name.contains_key(key) {
-                            OptionalParam::SetNoVal
-                        } else {
-                            iter.next();
-                            OptionalParam::Set(key.to_owned())
-                        };
-                        found_optionaloptions.insert(*id, opt_value);
+                    None => diagnostics.push(diagnostic(DiagnosticKind::MissingValue)),
+                },
+                RepeatedOption(id) => match take_value() {
+                    Some(opt_value) => {
+                        found_repeated.entry(*id).or_default().push(opt_value.to_owned());
                     }
+                    None => diagnostics.push(diagnostic(DiagnosticKind::MissingValue)),
+                },
+                OptionalOption(id) => {
+                    let opt_value = match take_value() {
+                        Some(opt_value) => OptionalParam::Set(opt_value.to_owned()),
+                        None => OptionalParam::SetNoVal,
+                    };
+                    found_optionaloptions.insert(*id, opt_value);
                 }
                 List(id, style) => {
                     // TODO: maybe extract to function
                     match style {
                         ListParseStyle::AllRemaining => {
-                            let list: Vec<String> = iter.map(str::to_owned).collect();
-                            found_list = Some((*id, list));
+                            let list: Vec<String> = iter.map(|(_, s)| s.to_owned()).collect();
+                            found_lists.insert(*id, list);
                             break 'parse_loop;
                         }
                         ListParseStyle::UntilDashPrefix => {
                             let mut list = Vec::<String>::new();
-                            while iter.peek().map_or(false, |s| !s.starts_with('-')) {
+                            while matches!(iter.peek(), Some((_, s)) if !s.starts_with('-')) {
                                 // unwrapping is safe, since peek already determined
                                 // a next element exists
-                                let s = iter.next().unwrap();
+                                let (_, s) = iter.next().unwrap();
                                 list.push(s.to_owned());
                             }
-                            found_list = Some((*id, list));
+                            if iter.peek().is_some() {
+                                diagnostics.push(diagnostic(DiagnosticKind::ListNotLast));
+                            }
+                            found_lists.insert(*id, list);
                         }
                     }
                 }
@@ -265,8 +588,33 @@ impl Parser {
             flags: found_flags,
             options: found_options,
             optionaloptions: found_optionaloptions,
-            list: found_list,
+            repeated_options: found_repeated,
+            lists: found_lists,
             remainder: remaining_args,
+            diagnostics,
+        }
+    }
+
+    /// Looks up the parsing rule for `arg`. Returns the name of the parameter, the rule and a
+    /// value, if `arg` has the form `name=value` and the parameter takes a value.
+    fn lookup<'s, 'v>(
+        &'s self,
+        arg: &'v str,
+    ) -> Option<(&'v str, &'s ArgumentType, Option<&'v str>)> {
+        if let Some(arg_type) = self.args_by_name.get(arg) {
+            return Some((arg, arg_type, None));
+        }
+        let (name, value) = arg.split_once('=')?;
+        let arg_type = self.args_by_name.get(name).filter(|t| t.takes_value())?;
+        Some((name, arg_type, Some(value)))
+    }
+
+    /// Returns `true` if `arg` starts with the prefix registered via `check_unknown_prefix`
+    /// and is not one of the known names. Registered parameters have to be checked before.
+    fn is_unknown(&self, arg: &str) -> bool {
+        match self.unknown_prefix {
+            Some((prefix, known)) => arg.starts_with(prefix) && !known.contains(&arg),
+            None => false,
         }
     }
 }
@@ -278,12 +626,13 @@ impl Default for Parser {
 }
 
 pub struct ParseResult<'s> {
-    // TODO list of parse errors?
     flags: HashSet<FlagId>,
     options: HashMap<OptionId, String>,
     optionaloptions: HashMap<OptionalOptionId, OptionalParam>,
-    list: Option<(ListId, Vec<String>)>,
+    repeated_options: HashMap<RepeatedOptionId, Vec<String>>,
+    lists: HashMap<ListId, Vec<String>>,
     remainder: Vec<&'s str>,
+    diagnostics: Vec<ParseDiagnostic>,
 }
 
 impl <'s> ParseResult<'s> {
@@ -302,21 +651,68 @@ impl <'s> ParseResult<'s> {
             .unwrap_or_default()
     }
 
+    /// Returns the value of the typed option converted to `T`, or `None` if the
+    /// option was not present. Returns an error if the value cannot be converted.
+    pub fn take_typed_option<T: OptionValue>(
+        &mut self,
+        arg: TypedOptionId<T>,
+    ) -> Result<Option<T>, OptionValueError> {
+        let value = match self.take_option(arg.id) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        T::parse_value(&value)
+            .map(Some)
+            .map_err(|message| OptionValueError {
+                option: arg.name,
+                value,
+                message,
+            })
+    }
+
+    /// Returns the values of all occurrences of the repeated option, in order.
+    /// The result is empty if the option was not present.
+    pub fn take_repeated_option(&mut self, arg: RepeatedOptionId) -> Vec<String> {
+        self.repeated_options.remove(&arg).unwrap_or_default()
+    }
+
+    /// Returns the values of all occurrences of the typed repeated option converted to `T`,
+    /// in order. Returns an error for the first value which cannot be converted.
+    pub fn take_typed_repeated_option<T: OptionValue>(
+        &mut self,
+        arg: TypedRepeatedOptionId<T>,
+    ) -> Result<Vec<T>, OptionValueError> {
+        self.take_repeated_option(arg.id)
+            .into_iter()
+            .map(|value| {
+                T::parse_value(&value).map_err(|message| OptionValueError {
+                    option: arg.name,
+                    value,
+                    message,
+                })
+            })
+            .collect()
+    }
+
     pub fn take_list(&mut self, arg: ListId) -> Option<Vec<String>> {
-        self.list
-            .take()
-            .filter(|(list_arg, _)| list_arg == &arg)
-            .map(|(_, list)| list)
+        self.lists.remove(&arg)
     }
 
     pub fn get_remainder(&self) -> Vec<&'s str> {
         self.remainder.clone()
     }
+
+    /// Returns the problems detected while parsing, in the order of the arguments.
+    pub fn diagnostics(&self) -> &[ParseDiagnostic] {
+        &self.diagnostics
+    }
 }
 
 #[cfg(test)]
 mod parser_test {
-    use super::{ListParseStyle, OptionalParam};
+    use super::{DiagnosticKind, ListParseStyle, OptionValue, OptionalParam, ParseDiagnostic};
+    use std::path::PathBuf;
+    use std::time::Duration;
     #[test]
     fn test_replace() {
         let mut o = Some(4);
@@ -380,6 +776,15 @@ mod parser_test {
         assert_eq!(console_value, OptionalParam::SetNoVal);
     }
 
+    #[test]
+    fn test_parsing_optional_option_last() {
+        let args = ["-clean", "-debug"];
+        let mut parser = super::Parser::new();
+        let debug = parser.add_optional_option("-debug");
+        let mut parse_result = parser.parse(args.iter().copied());
+        assert_eq!(parse_result.take_optional_option(debug), OptionalParam::SetNoVal);
+    }
+
     #[test]
     fn test_parsing_optional_option_set_not_set() {
         let args = vec!["-clean", "-application", "foo.bar"];
@@ -416,4 +821,155 @@ mod parser_test {
         expected.drain(1..=2);
         assert_eq!(expected, remainder);
     }
+
+    #[test]
+    fn test_diagnostic_missing_value() {
+        let args = ["-name", "-clean", "-application"];
+        let mut parser = super::Parser::new();
+        let name = parser.add_option("-name");
+        let _clean = parser.add_flag("-clean");
+        let _application = parser.add_option("-application");
+        let mut parse_result = parser.parse(args.iter().copied());
+        let kinds: Vec<(usize, DiagnosticKind)> =
+            parse_result.diagnostics().iter().map(|d| (d.index, d.kind)).collect();
+        assert_eq!(kinds, vec![(0, DiagnosticKind::MissingValue), (2, DiagnosticKind::MissingValue)]);
+        assert_eq!(parse_result.take_option(name), None);
+    }
+
+    #[test]
+    fn test_diagnostic_duplicate() {
+        let args = ["-name", "foo", "-name", "bar"];
+        let mut parser = super::Parser::new();
+        let name = parser.add_option("-name");
+        let mut parse_result = parser.parse(args.iter().copied());
+        let expected = ParseDiagnostic {
+            index: 2,
+            token: "-name".to_string(),
+            kind: DiagnosticKind::DuplicateOption,
+        };
+        assert_eq!(parse_result.diagnostics(), &[expected]);
+        assert_eq!(parse_result.take_option(name).unwrap(), "bar");
+    }
+
+    #[test]
+    fn test_diagnostic_unknown_prefix() {
+        let args = ["--launcher.foo", "--launcher.timeout", "--launcher.ini", "x.ini", "-data"];
+        let mut parser = super::Parser::new();
+        let _ini = parser.add_option("--launcher.ini");
+        parser.check_unknown_prefix("--launcher.", &["--launcher.timeout"]);
+        let parse_result = parser.parse(args.iter().copied());
+        let kinds: Vec<(usize, DiagnosticKind)> =
+            parse_result.diagnostics().iter().map(|d| (d.index, d.kind)).collect();
+        assert_eq!(kinds, vec![(0, DiagnosticKind::UnknownOption)]);
+        assert_eq!(parse_result.get_remainder(), vec!["--launcher.foo", "--launcher.timeout", "-data"]);
+    }
+
+    #[test]
+    fn test_diagnostic_list_not_last() {
+        let args = ["--launcher.openFile", "a.txt", "-clean"];
+        let mut parser = super::Parser::new();
+        let open_file = parser.add_list("--launcher.openFile", ListParseStyle::UntilDashPrefix);
+        let mut parse_result = parser.parse(args.iter().copied());
+        let kinds: Vec<DiagnosticKind> = parse_result.diagnostics().iter().map(|d| d.kind).collect();
+        assert_eq!(kinds, vec![DiagnosticKind::ListNotLast]);
+        assert_eq!(parse_result.take_list(open_file).unwrap(), vec!["a.txt".to_string()]);
+    }
+
+    #[test]
+    fn test_parsing_inline_values() {
+        let args = ["--launcher.timeout=30", "-console=1234", "-Dfoo=bar", "-clean=true"];
+        let mut parser = super::Parser::new();
+        let timeout = parser.add_option("--launcher.timeout");
+        let console = parser.add_optional_option("-console");
+        let clean = parser.add_flag("-clean");
+        let mut parse_result = parser.parse(args.iter().copied());
+        assert_eq!(parse_result.take_option(timeout).unwrap(), "30");
+        assert_eq!(parse_result.take_optional_option(console), OptionalParam::Set("1234".into()));
+        // flags do not take values, so these arguments are not parsed
+        assert!(!parse_result.take_flag(clean));
+        assert_eq!(parse_result.get_remainder(), vec!["-Dfoo=bar", "-clean=true"]);
+    }
+
+    #[test]
+    fn test_parsing_repeated_option() {
+        let args = ["-vm", "/opt/jdk11", "--launcher.include", "a.ini", "-vm=/opt/jdk8", "-clean"];
+        let mut parser = super::Parser::new();
+        let vm = parser.add_repeated_option("-vm");
+        let include = parser.add_repeated_option("--launcher.include");
+        let other = parser.add_repeated_option("--launcher.other");
+        let mut parse_result = parser.parse(args.iter().copied());
+        assert!(parse_result.diagnostics().is_empty());
+        assert_eq!(parse_result.take_repeated_option(vm), vec!["/opt/jdk11", "/opt/jdk8"]);
+        assert_eq!(parse_result.take_repeated_option(include), vec!["a.ini"]);
+        assert!(parse_result.take_repeated_option(other).is_empty());
+    }
+
+    #[test]
+    fn test_parsing_typed_repeated_option() {
+        let args = ["-port", "8080", "-port=8081", "-timeout", "1s", "-timeout", "x"];
+        let mut parser = super::Parser::new();
+        let port = parser.add_typed_repeated_option::<u16>("-port");
+        let timeout = parser.add_typed_repeated_option::<Duration>("-timeout");
+        let mut parse_result = parser.parse(args.iter().copied());
+        assert_eq!(parse_result.take_typed_repeated_option(port), Ok(vec![8080, 8081]));
+        let error = parse_result.take_typed_repeated_option(timeout).unwrap_err();
+        assert_eq!(error.option, "-timeout");
+        assert_eq!(error.value, "x");
+    }
+
+    #[test]
+    fn test_parsing_multiple_lists() {
+        let args = ["--launcher.openFile", "a.txt", "b.txt", "-vmargs", "-Xmx1g"];
+        let mut parser = super::Parser::new();
+        let open_file = parser.add_list("--launcher.openFile", ListParseStyle::UntilDashPrefix);
+        let vmargs = parser.add_list("-vmargs", ListParseStyle::AllRemaining);
+        let mut parse_result = parser.parse(args.iter().copied());
+        assert_eq!(parse_result.take_list(vmargs).unwrap(), vec!["-Xmx1g".to_string()]);
+        let files = parse_result.take_list(open_file).unwrap();
+        assert_eq!(files, vec!["a.txt".to_string(), "b.txt".to_string()]);
+    }
+
+    #[test]
+    fn test_parsing_typed_options() {
+        let args = ["--launcher.timeout", "30", "-port=8080", "-data", "/tmp/ws"];
+        let mut parser = super::Parser::new();
+        let timeout = parser.add_typed_option::<Duration>("--launcher.timeout");
+        let port = parser.add_typed_option::<u16>("-port");
+        let data = parser.add_typed_option::<PathBuf>("-data");
+        let missing = parser.add_typed_option::<u32>("-missing");
+        let mut parse_result = parser.parse(args.iter().copied());
+        assert_eq!(parse_result.take_typed_option(timeout), Ok(Some(Duration::from_secs(30))));
+        assert_eq!(parse_result.take_typed_option(port), Ok(Some(8080)));
+        assert_eq!(parse_result.take_typed_option(data), Ok(Some(PathBuf::from("/tmp/ws"))));
+        assert_eq!(parse_result.take_typed_option(missing), Ok(None));
+    }
+
+    #[test]
+    fn test_parsing_typed_option_invalid() {
+        let args = ["--launcher.timeout", "abc"];
+        let mut parser = super::Parser::new();
+        let timeout = parser.add_typed_option::<Duration>("--launcher.timeout");
+        let mut parse_result = parser.parse(args.iter().copied());
+        let err = parse_result.take_typed_option(timeout).unwrap_err();
+        assert_eq!(err.option, "--launcher.timeout");
+        assert_eq!(
+            err.to_string(),
+            "Invalid value 'abc' for option --launcher.timeout: expected a duration in seconds, e.g. 30 or 500ms"
+        );
+    }
+
+    #[test]
+    fn test_duration_units() {
+        assert_eq!(Duration::parse_value("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(Duration::parse_value("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(Duration::parse_value("15s"), Ok(Duration::from_secs(15)));
+        assert!(Duration::parse_value("-1").is_err());
+    }
+
+    #[test]
+    fn test_parse_choice() {
+        let choices = [("2", 2), ("3", 3)];
+        assert_eq!(super::parse_choice("3", &choices), Ok(3));
+        assert_eq!(super::parse_choice("4", &choices), Err("expected one of 2, 3".to_string()));
+    }
 }
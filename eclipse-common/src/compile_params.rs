@@ -23,6 +23,30 @@ static DEFAULT_OS_ARCH: Option<&str> = option_env!("DEFAULT_OS_ARCH");
 
 static DEFAULT_WS: Option<&str> = option_env!("DEFAULT_WS");
 
+/// The operating system, architecture and windowing system the launcher runs on
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Platform<'a> {
+    pub os: &'a str,
+    pub arch: &'a str,
+    pub ws: &'a str,
+}
+
+impl<'a> Platform<'a> {
+    /// Returns the default platform, with the values given via `-os`, `-arch`
+    /// and `-ws` replacing the corresponding defaults.
+    pub fn with_overrides(
+        os: Option<&'a str>,
+        arch: Option<&'a str>,
+        ws: Option<&'a str>,
+    ) -> Platform<'a> {
+        Platform {
+            os: os.unwrap_or_else(|| get_default_os()),
+            arch: arch.unwrap_or_else(|| get_default_arch()),
+            ws: ws.unwrap_or_else(|| get_default_ws()),
+        }
+    }
+}
+
 // make const as soon as get_default_os and get_default_arch are const
 pub fn is_macos_non_x86_64() -> bool {
     get_default_os() == "macosx" && get_default_arch() != "x86_64"
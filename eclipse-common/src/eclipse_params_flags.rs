@@ -12,13 +12,13 @@
  *     Max Bureck (Fraunhofer FOKUS)
  *******************************************************************************/
 
-//! This module provides the constant flag values of parameters passed to 
+//! This module provides the constant flag values of parameters passed to
 //! The launcher.
 
 // Argument names for native launcher, passed on to java launcher
 pub const CONSOLE: &str = "-console";
 pub const CONSOLELOG: &str = "-consoleLog";
-pub const  DEBUG: &str = "-debug";
+pub const DEBUG: &str = "-debug";
 pub const OS: &str = "-os";
 pub const OSARCH: &str = "-arch";
 pub const NOSPLASH: &str = "-nosplash";
@@ -35,14 +35,35 @@ pub const TIMEOUT: &str = "--launcher.timeout";
 pub const LIBRARY: &str = "--launcher.library";
 pub const SUPRESSERRORS: &str = "--launcher.suppressErrors";
 pub const INI: &str = "--launcher.ini";
+pub const INCLUDE: &str = "--launcher.include";
 pub const APPEND_VMARGS: &str = "--launcher.appendVmargs";
 pub const OVERRIDE_VMARGS: &str = "--launcher.overrideVmargs";
 pub const SECOND_THREAD: &str = "--launcher.secondThread";
 pub const PERM_GEN: &str = "--launcher.XXMaxPermSize";
 pub const GTK_VERSION: &str = "--launcher.GTK_version";
+pub const STRICT: &str = "--launcher.strict";
+pub const CONFIG: &str = "--launcher.config";
+pub const HELP: &str = "--launcher.help";
+pub const PROFILE: &str = "--launcher.profile";
+pub const RESTART_LIMIT: &str = "--launcher.restartLimit";
+pub const RESTART_WINDOW: &str = "--launcher.restartWindow";
+pub const OOM_RETRY: &str = "--launcher.oomRetry";
+pub const STARTUP_TIMEOUT: &str = "--launcher.startupTimeout";
+pub const RLIMIT: &str = "--launcher.rlimit";
+pub const NICE: &str = "--launcher.nice";
+pub const IO_PRIORITY: &str = "--launcher.ioPriority";
+pub const CPU_AFFINITY: &str = "--launcher.cpuAffinity";
+pub const HEAP_PERCENT: &str = "--launcher.heapPercent";
+pub const ENV: &str = "--launcher.env";
+pub const UNSET_ENV: &str = "--launcher.unsetEnv";
+pub const CLEAN_ENV: &str = "--launcher.cleanEnv";
+pub const KEEP_ENV: &str = "--launcher.keepEnv";
 pub const VMARGS: &str = "-vmargs";
 
+/// Prefix of all options handled by the native launcher
+pub const LAUNCHER_PREFIX: &str = "--launcher.";
+
 // Only needed for the java launcher
 pub const JAR: &str = "-jar";
 pub const LAUNCHER: &str = "-launcher";
-pub const EXITDATA: &str = "-exitdata";
\ No newline at end of file
+pub const EXITDATA: &str = "-exitdata";
@@ -0,0 +1,206 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides `filter_conditional_blocks`, which evaluates platform
+//! conditional blocks in ini files:
+//!
+//! ```text
+//! [if os=linux arch=aarch64]
+//! -vm
+//! /usr/lib/jvm/java-17-arm64/bin
+//! [else]
+//! -vm
+//! /usr/lib/jvm/java-17/bin
+//! [endif]
+//! ```
+//!
+//! A condition consists of `key=value` terms separated by whitespace, all of which have
+//! to match. Supported keys are `os`, `arch` and `ws`. A term may list alternative values
+//! separated by `,` (`arch=x86_64,aarch64`) and `!=` negates a term (`ws!=gtk`).
+//! Blocks can be nested, the `[else]` part is optional.
+
+use crate::compile_params::Platform;
+use std::fmt;
+
+const IF_START: &str = "[if ";
+const BLOCK_END: char = ']';
+const ELSE: &str = "[else]";
+const ENDIF: &str = "[endif]";
+const VALUE_SEPARATOR: char = ',';
+
+/// Errors in conditional blocks
+#[derive(Debug, Eq, PartialEq)]
+pub enum ConditionError {
+    /// A term of a condition is not in the form `key=value` or `key!=value`
+    InvalidTerm(String),
+
+    /// A term of a condition uses a key other than `os`, `arch` and `ws`
+    UnknownKey(String),
+
+    /// An `[else]` or `[endif]` line without matching `[if ...]` line
+    UnmatchedLine(String),
+
+    /// An `[if ...]` block is missing its `[endif]` line
+    Unterminated,
+}
+
+impl fmt::Display for ConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ConditionError::*;
+        match self {
+            InvalidTerm(term) => write!(f, "Condition '{}' is not in the form key=value", term),
+            UnknownKey(key) => write!(
+                f,
+                "Condition key '{}' is unknown, expected os, arch or ws",
+                key
+            ),
+            UnmatchedLine(line) => write!(f, "{} without matching [if ...]", line),
+            Unterminated => write!(f, "[if ...] is missing a closing {}", ENDIF),
+        }
+    }
+}
+
+impl std::error::Error for ConditionError {}
+
+/// An `[if ...]` block the current line is located in
+struct Block {
+    matches: bool,
+    in_else: bool,
+}
+
+impl Block {
+    fn is_active(&self) -> bool {
+        self.matches != self.in_else
+    }
+}
+
+/// Returns the `lines` not located in conditional blocks, or in blocks whose condition
+/// matches `platform` (or in the `[else]` part if it does not match). The lines denoting
+/// blocks are removed. See module documentation for the syntax of conditions.
+pub(super) fn filter_conditional_blocks(
+    lines: impl Iterator<Item = String>,
+    platform: &Platform,
+) -> Result<Vec<String>, ConditionError> {
+    let mut result = Vec::new();
+    let mut blocks: Vec<Block> = Vec::new();
+    for line in lines {
+        let trimmed = line.trim();
+        if let Some(condition) = trimmed.strip_prefix(IF_START) {
+            let condition = match condition.strip_suffix(BLOCK_END) {
+                Some(condition) => condition,
+                None => return Err(ConditionError::InvalidTerm(trimmed.to_string())),
+            };
+            blocks.push(Block {
+                matches: condition_matches(condition, platform)?,
+                in_else: false,
+            });
+        } else if trimmed == ELSE {
+            match blocks.last_mut() {
+                Some(block) if !block.in_else => block.in_else = true,
+                _ => return Err(ConditionError::UnmatchedLine(ELSE.to_string())),
+            }
+        } else if trimmed == ENDIF {
+            if blocks.pop().is_none() {
+                return Err(ConditionError::UnmatchedLine(ENDIF.to_string()));
+            }
+        } else if blocks.iter().all(Block::is_active) {
+            result.push(line);
+        }
+    }
+    if blocks.is_empty() {
+        Ok(result)
+    } else {
+        Err(ConditionError::Unterminated)
+    }
+}
+
+/// Returns `true` if all terms of `condition` match `platform`.
+fn condition_matches(condition: &str, platform: &Platform) -> Result<bool, ConditionError> {
+    let mut matches = true;
+    // evaluate all terms, so invalid terms are reported regardless of the platform
+    for term in condition.split_whitespace() {
+        matches &= term_matches(term, platform)?;
+    }
+    Ok(matches)
+}
+
+fn term_matches(term: &str, platform: &Platform) -> Result<bool, ConditionError> {
+    let (key, values, negated) = match term.split_once("!=") {
+        Some((key, values)) => (key, values, true),
+        None => match term.split_once('=') {
+            Some((key, values)) => (key, values, false),
+            None => return Err(ConditionError::InvalidTerm(term.to_string())),
+        },
+    };
+    let actual = match key {
+        "os" => platform.os,
+        "arch" => platform.arch,
+        "ws" => platform.ws,
+        _ => return Err(ConditionError::UnknownKey(key.to_string())),
+    };
+    let is_listed = values.split(VALUE_SEPARATOR).any(|value| value == actual);
+    Ok(is_listed != negated)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{filter_conditional_blocks, ConditionError};
+    use crate::compile_params::Platform;
+
+    const LINUX_ARM: Platform<'static> = Platform {
+        os: "linux",
+        arch: "aarch64",
+        ws: "gtk",
+    };
+
+    fn filter(content: &str) -> Result<Vec<String>, ConditionError> {
+        filter_conditional_blocks(content.lines().map(str::to_string), &LINUX_ARM)
+    }
+
+    #[test]
+    fn test_filter_blocks() {
+        let content = "-name\nFoo\n\
+                       [if os=linux arch=x86_64]\n-vm\nx64\n[else]\n-vm\nother\n[endif]\n\
+                       [if arch=x86_64,aarch64 ws!=cocoa]\n-showsplash\n[endif]\n";
+        let expected = ["-name", "Foo", "-vm", "other", "-showsplash"];
+        assert_eq!(filter(content).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_nested_blocks() {
+        let content = "[if os=linux]\n[if ws=gtk]\n--launcher.GTK_version\n3\n[endif]\n[endif]\n\
+                       [if os=win32]\n[if ws=gtk]\n-nosplash\n[endif]\n[endif]\n";
+        assert_eq!(filter(content).unwrap(), ["--launcher.GTK_version", "3"]);
+    }
+
+    #[test]
+    fn test_condition_errors() {
+        let unknown = filter("[if cpu=arm]\n[endif]\n");
+        assert_eq!(unknown, Err(ConditionError::UnknownKey("cpu".to_string())));
+        let invalid = filter("[if linux]\n[endif]\n");
+        assert_eq!(
+            invalid,
+            Err(ConditionError::InvalidTerm("linux".to_string()))
+        );
+        assert_eq!(
+            filter("[if os=linux]\n-clean\n"),
+            Err(ConditionError::Unterminated)
+        );
+        let unmatched = filter("[endif]\n");
+        assert_eq!(
+            unmatched,
+            Err(ConditionError::UnmatchedLine("[endif]".to_string()))
+        );
+    }
+}
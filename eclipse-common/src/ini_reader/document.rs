@@ -0,0 +1,616 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides the type `IniDocument`, a model of a launcher ini file
+//! which allows modifying launcher options and VM arguments while keeping
+//! comments, blank lines and the order of all other lines intact.
+//!
+//! Example:
+//! ```
+//! use eclipse_common::ini_reader::IniDocument;
+//!
+//! let mut doc = IniDocument::parse("# heap\n-vmargs\n-Xmx1g\n");
+//! doc.set_launcher_option("-name", "Foo");
+//! doc.set_vm_arg("-Xmx4g");
+//! assert_eq!(doc.to_string(), "# heap\n-name\nFoo\n-vmargs\n-Xmx4g\n");
+//! ```
+
+use super::profile_section_name;
+use crate::eclipse_params_flags::{VM, VMARGS};
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io::prelude::*;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+const COMMENT_PREFIX: char = '#';
+const BYTE_ORDER_MARK: char = '\u{feff}';
+const SYSTEM_PROPERTY_PREFIX: &str = "-D";
+const XX_PREFIX: &str = "-XX:";
+const SIZE_OPTIONS: [&str; 4] = ["-Xmx", "-Xms", "-Xss", "-Xmn"];
+
+/// Advanced options which add to previous occurrences instead of overriding them
+const CUMULATIVE_XX_OPTIONS: [&str; 5] = [
+    "CompileCommand",
+    "CompileOnly",
+    "DisableIntrinsic",
+    "OnError",
+    "OnOutOfMemoryError",
+];
+
+/// A launcher ini file, holding all lines including comments and blank lines.
+/// Lines that are neither blank nor comments (`#`, optionally indented) are called
+/// settings. Settings in front of the first `-vmargs` line are launcher options,
+/// settings after it are VM arguments. Leading and trailing whitespace of settings
+/// is ignored. Methods reading and modifying launcher options and VM arguments only
+/// consider the lines in front of the first profile section (`[profile:<name>]`).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IniDocument {
+    lines: Vec<String>,
+    line_ending: &'static str,
+    trailing_line_ending: bool,
+    byte_order_mark: bool,
+    invalid_lines: Vec<usize>,
+}
+
+impl Default for IniDocument {
+    fn default() -> Self {
+        IniDocument {
+            lines: Vec::new(),
+            line_ending: "\n",
+            trailing_line_ending: true,
+            byte_order_mark: false,
+            invalid_lines: Vec::new(),
+        }
+    }
+}
+
+impl IniDocument {
+    /// Parses the content of an ini file. The line ending style (`\n` or `\r\n`) and a
+    /// leading byte order mark are detected from the content and kept when the document
+    /// is written.
+    pub fn parse(content: &str) -> IniDocument {
+        let line_ending = if content.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let without_bom = content.strip_prefix(BYTE_ORDER_MARK);
+        IniDocument {
+            lines: without_bom
+                .unwrap_or(content)
+                .lines()
+                .map(str::to_string)
+                .collect(),
+            line_ending,
+            trailing_line_ending: content.is_empty() || content.ends_with('\n'),
+            byte_order_mark: without_bom.is_some(),
+            invalid_lines: Vec::new(),
+        }
+    }
+
+    /// Reads the ini file located at `path`, which is expected to be UTF-8 encoded. Lines that
+    /// are not valid UTF-8 are decoded lossily, their line numbers are available via
+    /// `invalid_lines`. Note that saving the document writes these lines as decoded.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<IniDocument, Error> {
+        let bytes = fs::read(path)?;
+        if let Ok(content) = std::str::from_utf8(&bytes) {
+            return Ok(IniDocument::parse(content));
+        }
+        let mut invalid_lines = Vec::new();
+        let mut content = String::with_capacity(bytes.len());
+        for (index, line) in bytes.split(|b| *b == b'\n').enumerate() {
+            if index > 0 {
+                content.push('\n');
+            }
+            let decoded = String::from_utf8_lossy(line);
+            if let Cow::Owned(_) = decoded {
+                invalid_lines.push(index + 1);
+            }
+            content.push_str(&decoded);
+        }
+        let mut doc = IniDocument::parse(&content);
+        doc.invalid_lines = invalid_lines;
+        Ok(doc)
+    }
+
+    /// Returns the (1-based) numbers of lines that were not valid
+    /// UTF-8 and were decoded lossily by `load`.
+    pub fn invalid_lines(&self) -> &[usize] {
+        &self.invalid_lines
+    }
+
+    /// Writes the document to `path`. The content is written to a temporary file in the
+    /// same directory first, which then replaces the file at `path`. So readers never see
+    /// a partially written file. Permissions of an existing file are preserved.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Path has no file name"))?;
+        let mut tmp_name = OsString::from(".");
+        tmp_name.push(file_name);
+        tmp_name.push(".tmp");
+        let tmp_path = path.with_file_name(tmp_name);
+
+        let result = self
+            .write_file(&tmp_path, path)
+            .and_then(|_| fs::rename(&tmp_path, path));
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result
+    }
+
+    fn write_file(&self, tmp_path: &Path, target_path: &Path) -> Result<(), Error> {
+        let mut file = fs::File::create(tmp_path)?;
+        file.write_all(self.to_string().as_bytes())?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(target_path) {
+            fs::set_permissions(tmp_path, metadata.permissions())?;
+        }
+        Ok(())
+    }
+
+    /// Returns all settings (lines that are neither blank nor comments) in order,
+    /// with surrounding whitespace removed. These are the lines the launcher interprets.
+    pub fn values(&self) -> impl Iterator<Item = &str> {
+        self.lines
+            .iter()
+            .filter(|line| is_setting(line))
+            .map(|line| line.trim())
+    }
+
+    /// Consumes the document and returns all settings, see `values`.
+    pub fn into_values(self) -> impl Iterator<Item = String> {
+        self.lines
+            .into_iter()
+            .filter(|line| is_setting(line))
+            .map(|line| line.trim().to_string())
+    }
+
+    /// Returns the launcher options, which are the settings in front of `-vmargs`.
+    pub fn launcher_args(&self) -> Vec<&str> {
+        self.launcher_indices()
+            .into_iter()
+            .map(|i| self.value(i))
+            .collect()
+    }
+
+    /// Returns the VM arguments, which are the settings after `-vmargs`.
+    pub fn vm_args(&self) -> Vec<&str> {
+        self.vm_indices()
+            .into_iter()
+            .map(|i| self.value(i))
+            .collect()
+    }
+
+    /// Returns the value of the last occurrence of the launcher option `name`,
+    /// which is the value the launcher uses.
+    pub fn launcher_option(&self, name: &str) -> Option<&str> {
+        self.find_launcher_option(name, true)
+            .into_iter()
+            .filter_map(|(_, value_index)| value_index)
+            .next_back()
+            .map(|i| self.value(i))
+    }
+
+    /// Returns `true` if the launcher flag `name` is present.
+    pub fn has_launcher_flag(&self, name: &str) -> bool {
+        !self.find_launcher_option(name, false).is_empty()
+    }
+
+    /// Sets the launcher option `name` to `value`. The first occurrence of the option is
+    /// updated in place and further occurrences are removed. If the option is not present,
+    /// it is added in front of `-vmargs`.
+    pub fn set_launcher_option(&mut self, name: &str, value: &str) {
+        let occurrences = self.find_launcher_option(name, true);
+        match occurrences.split_first() {
+            Some(((name_index, value_index), duplicates)) => {
+                self.remove_occurrences(duplicates);
+                match value_index {
+                    Some(i) => self.lines[*i] = value.to_string(),
+                    None => self.lines.insert(name_index + 1, value.to_string()),
+                }
+            }
+            None => {
+                let index = self.launcher_insert_index();
+                self.lines.insert(index, name.to_string());
+                self.lines.insert(index + 1, value.to_string());
+            }
+        }
+    }
+
+    /// Adds the launcher flag `name` in front of `-vmargs`, if it is not present yet.
+    pub fn set_launcher_flag(&mut self, name: &str) {
+        if !self.has_launcher_flag(name) {
+            let index = self.launcher_insert_index();
+            self.lines.insert(index, name.to_string());
+        }
+    }
+
+    /// Removes all occurrences of the launcher option `name` together with their values.
+    /// Returns `true` if the option was present.
+    pub fn remove_launcher_option(&mut self, name: &str) -> bool {
+        let occurrences = self.find_launcher_option(name, true);
+        self.remove_occurrences(&occurrences);
+        !occurrences.is_empty()
+    }
+
+    /// Removes all occurrences of the launcher flag `name`.
+    /// Returns `true` if the flag was present.
+    pub fn remove_launcher_flag(&mut self, name: &str) -> bool {
+        let occurrences = self.find_launcher_option(name, false);
+        self.remove_occurrences(&occurrences);
+        !occurrences.is_empty()
+    }
+
+    /// Returns the VM specified via `-vm`.
+    pub fn vm(&self) -> Option<&str> {
+        self.launcher_option(VM)
+    }
+
+    /// Sets the VM specified via `-vm` to `vm_path`.
+    pub fn set_vm(&mut self, vm_path: &str) {
+        self.set_launcher_option(VM, vm_path)
+    }
+
+    /// Removes the `-vm` option. Returns `true` if it was present.
+    pub fn remove_vm(&mut self) -> bool {
+        self.remove_launcher_option(VM)
+    }
+
+    /// Returns the last VM argument with the same key as `arg`,
+    /// which is the one the JVM uses. See `vm_arg_key`.
+    pub fn vm_arg(&self, arg: &str) -> Option<&str> {
+        let key = vm_arg_key(arg);
+        self.vm_args()
+            .into_iter()
+            .rfind(|vm_arg| vm_arg_key(vm_arg) == key)
+    }
+
+    /// Sets the VM argument `arg`. The first VM argument with the same key (see `vm_arg_key`)
+    /// is replaced in place and further ones are removed. If no VM argument with the same key
+    /// is present, `arg` is appended to the VM arguments, adding `-vmargs` if needed.
+    pub fn set_vm_arg(&mut self, arg: &str) {
+        let matches = self.find_vm_arg(arg);
+        match matches.split_first() {
+            Some((first, duplicates)) => {
+                self.remove_lines(duplicates);
+                self.lines[*first] = arg.to_string();
+            }
+            None => {
+                let last_vm_line = self.vm_indices().last().copied();
+                let index = match last_vm_line.or_else(|| self.vmargs_index()) {
+                    Some(i) => i + 1,
+                    None => {
+                        let base_end = self.base_end();
+                        self.lines.insert(base_end, VMARGS.to_string());
+                        base_end + 1
+                    }
+                };
+                self.lines.insert(index, arg.to_string());
+            }
+        }
+    }
+
+    /// Removes all VM arguments with the same key as `arg`, see `vm_arg_key`.
+    /// Returns `true` if any VM argument was removed.
+    pub fn remove_vm_arg(&mut self, arg: &str) -> bool {
+        let matches = self.find_vm_arg(arg);
+        self.remove_lines(&matches);
+        !matches.is_empty()
+    }
+
+    fn value(&self, index: usize) -> &str {
+        self.lines[index].trim()
+    }
+
+    /// Returns the line index of the first profile section, or the number
+    /// of lines if there is none
+    fn base_end(&self) -> usize {
+        (0..self.lines.len())
+            .find(|i| profile_section_name(self.lines[*i].trim()).is_some())
+            .unwrap_or(self.lines.len())
+    }
+
+    /// Returns the line index of the first `-vmargs` setting
+    fn vmargs_index(&self) -> Option<usize> {
+        (0..self.base_end()).find(|i| is_setting(&self.lines[*i]) && self.value(*i) == VMARGS)
+    }
+
+    /// Returns the line indices of all settings in front of `-vmargs`
+    fn launcher_indices(&self) -> Vec<usize> {
+        let end = self.vmargs_index().unwrap_or_else(|| self.base_end());
+        (0..end).filter(|i| is_setting(&self.lines[*i])).collect()
+    }
+
+    /// Returns the line indices of all settings after `-vmargs`
+    fn vm_indices(&self) -> Vec<usize> {
+        match self.vmargs_index() {
+            Some(start) => (start + 1..self.base_end())
+                .filter(|i| is_setting(&self.lines[*i]))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// New launcher options are added in front of `-vmargs`, or at the end
+    fn launcher_insert_index(&self) -> usize {
+        self.vmargs_index().unwrap_or_else(|| self.base_end())
+    }
+
+    /// Returns the line indices of all occurrences of launcher option `name`. If `with_value`
+    /// is `true`, the line index of the following value is returned as well, if present.
+    fn find_launcher_option(&self, name: &str, with_value: bool) -> Vec<(usize, Option<usize>)> {
+        let mut result = Vec::new();
+        let mut indices = self.launcher_indices().into_iter().peekable();
+        while let Some(index) = indices.next() {
+            if self.value(index) != name {
+                continue;
+            }
+            let value_index = if with_value { indices.next() } else { None };
+            result.push((index, value_index));
+        }
+        result
+    }
+
+    /// Returns the line indices of all VM arguments with the same key as `arg`
+    fn find_vm_arg(&self, arg: &str) -> Vec<usize> {
+        let key = vm_arg_key(arg);
+        self.vm_indices()
+            .into_iter()
+            .filter(|i| vm_arg_key(self.value(*i)) == key)
+            .collect()
+    }
+
+    fn remove_occurrences(&mut self, occurrences: &[(usize, Option<usize>)]) {
+        let indices: Vec<usize> = occurrences
+            .iter()
+            .flat_map(|(name_index, value_index)| Some(*name_index).into_iter().chain(*value_index))
+            .collect();
+        self.remove_lines(&indices);
+    }
+
+    fn remove_lines(&mut self, indices: &[usize]) {
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        // remove from the back, so the remaining indices stay valid
+        for index in indices.into_iter().rev() {
+            self.lines.remove(index);
+        }
+    }
+}
+
+impl fmt::Display for IniDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.byte_order_mark {
+            write!(f, "{}", BYTE_ORDER_MARK)?;
+        }
+        f.write_str(&self.lines.join(self.line_ending))?;
+        if self.trailing_line_ending && !self.lines.is_empty() {
+            f.write_str(self.line_ending)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns `true` if `line` is neither blank nor a comment
+fn is_setting(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && !line.starts_with(COMMENT_PREFIX)
+}
+
+/// Returns the key of VM argument `arg`. VM arguments with the same key configure
+/// the same JVM setting, so only the last one takes effect. Keys are:
+/// * `-Xmx`, `-Xms`, `-Xss` and `-Xmn` for memory sizes, e.g. `-Xmx` for `-Xmx4g`.
+///   The size has to follow directly, so e.g. OpenJ9's `-Xmso1m` is not a `-Xms` option.
+/// * `-D<name>` for system properties, e.g. `-Dfoo` for `-Dfoo=bar`
+/// * `-XX:<name>` for advanced options, e.g. `-XX:UseG1GC` for `-XX:+UseG1GC`
+///   and `-XX:MaxPermSize` for `-XX:MaxPermSize=256m`. Options which may be given
+///   multiple times, like `-XX:CompileCommand=...`, use the whole argument as key.
+/// * The part in front of `=` for all other arguments, or the argument itself
+pub fn vm_arg_key(arg: &str) -> String {
+    let until_equals = |s: &str| s.split('=').next().unwrap_or_default().to_string();
+    if let Some(xx_option) = arg.strip_prefix(XX_PREFIX) {
+        let name = until_equals(xx_option.trim_start_matches(['+', '-']));
+        if CUMULATIVE_XX_OPTIONS.contains(&name.as_str()) {
+            return arg.to_string();
+        }
+        return format!("{}{}", XX_PREFIX, name);
+    }
+    if arg.starts_with(SYSTEM_PROPERTY_PREFIX) {
+        return until_equals(arg);
+    }
+    if let Some(size_option) = size_option(arg) {
+        return size_option.to_string();
+    }
+    until_equals(arg)
+}
+
+/// Returns whether VM argument `arg` overrides previous VM arguments with the same key
+/// (see `vm_arg_key`). This is the case for memory sizes, system properties and
+/// advanced options, except the ones which may be given multiple times.
+pub fn is_overriding_vm_arg(arg: &str) -> bool {
+    if let Some(xx_option) = arg.strip_prefix(XX_PREFIX) {
+        let name = xx_option.trim_start_matches(['+', '-']);
+        let name = name.split('=').next().unwrap_or_default();
+        return !CUMULATIVE_XX_OPTIONS.contains(&name);
+    }
+    arg.starts_with(SYSTEM_PROPERTY_PREFIX) || size_option(arg).is_some()
+}
+
+/// Returns the memory size option `arg` starts with, if it is directly followed
+/// by the size or nothing
+fn size_option(arg: &str) -> Option<&'static str> {
+    SIZE_OPTIONS.iter().copied().find(|option| {
+        arg.strip_prefix(option)
+            .is_some_and(|size| size.is_empty() || size.starts_with(|c: char| c.is_ascii_digit()))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_overriding_vm_arg, vm_arg_key, IniDocument};
+
+    const INI: &str = "\
+# product configuration
+-startup
+plugins/org.eclipse.equinox.launcher.jar
+--launcher.appendVmargs
+
+-vmargs
+# heap settings
+-Xmx1g
+-Dosgi.requiredJavaVersion=1.8
+";
+
+    #[test]
+    fn test_round_trip() {
+        let doc = IniDocument::parse(INI);
+        assert_eq!(doc.to_string(), INI);
+        let crlf = INI.replace('\n', "\r\n");
+        assert_eq!(IniDocument::parse(&crlf).to_string(), crlf);
+    }
+
+    #[test]
+    fn test_sections() {
+        let doc = IniDocument::parse(INI);
+        assert_eq!(
+            doc.launcher_args(),
+            [
+                "-startup",
+                "plugins/org.eclipse.equinox.launcher.jar",
+                "--launcher.appendVmargs"
+            ]
+        );
+        assert_eq!(doc.vm_args(), ["-Xmx1g", "-Dosgi.requiredJavaVersion=1.8"]);
+        assert_eq!(
+            doc.launcher_option("-startup"),
+            Some("plugins/org.eclipse.equinox.launcher.jar")
+        );
+        assert!(doc.has_launcher_flag("--launcher.appendVmargs"));
+    }
+
+    #[test]
+    fn test_set_and_remove_launcher_options() {
+        let mut doc = IniDocument::parse(INI);
+        doc.set_vm("/opt/jdk/bin/java");
+        doc.set_launcher_option("-startup", "launcher.jar");
+        assert!(doc.remove_launcher_flag("--launcher.appendVmargs"));
+        let expected = "\
+# product configuration
+-startup
+launcher.jar
+
+-vm
+/opt/jdk/bin/java
+-vmargs
+# heap settings
+-Xmx1g
+-Dosgi.requiredJavaVersion=1.8
+";
+        assert_eq!(doc.to_string(), expected);
+        assert!(doc.remove_vm());
+        assert_eq!(doc.vm(), None);
+    }
+
+    #[test]
+    fn test_set_vm_args() {
+        let mut doc = IniDocument::parse(INI);
+        doc.set_vm_arg("-Xmx4g");
+        doc.set_vm_arg("-Dosgi.requiredJavaVersion=11");
+        doc.set_vm_arg("-XX:+UseG1GC");
+        assert_eq!(
+            doc.vm_args(),
+            ["-Xmx4g", "-Dosgi.requiredJavaVersion=11", "-XX:+UseG1GC"]
+        );
+        assert!(doc.remove_vm_arg("-XX:-UseG1GC"));
+        assert_eq!(doc.vm_arg("-Xmx"), Some("-Xmx4g"));
+    }
+
+    #[test]
+    fn test_set_vm_arg_adds_vmargs() {
+        let mut doc = IniDocument::parse("-showsplash\n");
+        doc.set_vm_arg("-Xmx2g");
+        assert_eq!(doc.to_string(), "-showsplash\n-vmargs\n-Xmx2g\n");
+    }
+
+    #[test]
+    fn test_save() {
+        let path =
+            std::env::temp_dir().join(format!("eclipse_doc_test_{}.ini", std::process::id()));
+        let mut doc = IniDocument::parse(INI);
+        doc.set_vm_arg("-Xmx2g");
+        doc.save(&path).unwrap();
+        let loaded = IniDocument::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded, doc);
+    }
+
+    #[test]
+    fn test_decoding() {
+        let content = "\u{feff}  -startup \r\n  # indented comment\r\n\tlauncher.jar\r\n";
+        let doc = IniDocument::parse(content);
+        let values: Vec<&str> = doc.values().collect();
+        assert_eq!(values, ["-startup", "launcher.jar"]);
+        assert_eq!(doc.to_string(), content);
+    }
+
+    #[test]
+    fn test_load_invalid_utf8() {
+        let path =
+            std::env::temp_dir().join(format!("eclipse_doc_utf8_{}.ini", std::process::id()));
+        std::fs::write(&path, b"-name\nF\xf6\xf6\n-vmargs\n").unwrap();
+        let doc = IniDocument::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(doc.invalid_lines(), &[2]);
+        assert_eq!(doc.launcher_option("-name"), Some("F\u{fffd}\u{fffd}"));
+    }
+
+    #[test]
+    fn test_vm_arg_key() {
+        assert_eq!(vm_arg_key("-Xmx4g"), "-Xmx");
+        assert_eq!(vm_arg_key("-Dfoo=bar"), "-Dfoo");
+        assert_eq!(vm_arg_key("-XX:+UseG1GC"), "-XX:UseG1GC");
+        assert_eq!(vm_arg_key("-XX:MaxPermSize=256m"), "-XX:MaxPermSize");
+        assert_eq!(vm_arg_key("-ea"), "-ea");
+        assert_eq!(vm_arg_key("-Xms"), "-Xms");
+        assert_eq!(vm_arg_key("-Xmso1m"), "-Xmso1m");
+        let compile_command = "-XX:CompileCommand=exclude,java/lang/String.indexOf";
+        assert_eq!(vm_arg_key(compile_command), compile_command);
+    }
+
+    #[test]
+    fn test_is_overriding_vm_arg() {
+        assert!(is_overriding_vm_arg("-Xms512m"));
+        assert!(is_overriding_vm_arg("-Dfoo=bar"));
+        assert!(is_overriding_vm_arg("-XX:+UseG1GC"));
+        assert!(!is_overriding_vm_arg("-Xmso1m"));
+        assert!(!is_overriding_vm_arg("-XX:CompileCommand=quiet"));
+        assert!(!is_overriding_vm_arg("-ea"));
+    }
+
+    #[test]
+    fn test_profile_sections_untouched() {
+        let mut doc = IniDocument::parse("-name\nFoo\n[profile:profiling]\n-vmargs\n-Xmx4g\n");
+        assert!(doc.vm_args().is_empty());
+        doc.set_vm_arg("-Xmx1g");
+        doc.set_launcher_flag("--launcher.appendVmargs");
+        let expected = "-name\nFoo\n--launcher.appendVmargs\n-vmargs\n-Xmx1g\n[profile:profiling]\n-vmargs\n-Xmx4g\n";
+        assert_eq!(doc.to_string(), expected);
+    }
+}
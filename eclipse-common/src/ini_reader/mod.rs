@@ -0,0 +1,1006 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides the public method `read_ini`, which allows reading the
+//! launcher configuration file, which is either located relative to the executable,
+//! or on a user specified location.
+//!
+//! The function `read_ini_chain` reads the product configuration file together with
+//! system wide and per-user configuration files, see `IniLayer` for the precedence
+//! rules between these files. Variables in lines of these files are expanded, see
+//! module `ini_variables` for the supported variables. Other files can be included
+//! via `--launcher.include <path>`.
+//!
+//! Files of the chain can contain profile sections, starting with a `[profile:<name>]`
+//! line and ending at the next profile section. A profile section is structured like
+//! an ini file itself: launcher options, optionally followed by `-vmargs` and VM
+//! arguments. The options and VM arguments of the selected profile (see `select_profile`)
+//! follow the ones in front of the first profile section, so they override them.
+//! Sections of other profiles are ignored. Profile sections are not recognized in
+//! included files.
+//!
+//! Lines can be restricted to platforms via conditional blocks (`[if os=linux]` ...
+//! `[endif]`), see module `conditions`. Lines of blocks not matching the platform
+//! are dropped before any other processing.
+//!
+//! Tools modifying an ini file should use `IniDocument`, which keeps comments and
+//! the order of lines. The functions of this module read lines via this type as well.
+
+mod conditions;
+mod document;
+
+pub use conditions::ConditionError;
+pub use document::{is_overriding_vm_arg, vm_arg_key, IniDocument};
+
+use crate::compile_params::Platform;
+use crate::eclipse_params_flags::{ENV, INCLUDE, UNSET_ENV, VMARGS};
+use crate::ini_variables::{expand_variables, VariableContext, VariableError};
+use conditions::filter_conditional_blocks;
+use std::fmt;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Directory holding the system wide configuration
+const SYSTEM_CONFIG_DIR: &str = "/etc";
+
+const PROFILE_SECTION_START: &str = "[profile:";
+const PROFILE_SECTION_END: char = ']';
+
+/// Environment variable selecting a profile, if not selected via `--launcher.profile`
+pub const PROFILE_ENV: &str = "ECLIPSE_PROFILE";
+
+/// Launcher options whose values are not expanded while reading, whether given on the
+/// next line or in the form `<option>=<value>`. Variables in the values of `--launcher.env`
+/// are expanded against the environment of the JVM built so far.
+const UNEXPANDED_VALUE_OPTIONS: [&str; 2] = [ENV, UNSET_ENV];
+
+/// Reads the ini config file either from a user specified location,
+/// if `user_defined_config` is `Some`, or otherwise from a location relative
+/// to the executable. The path to the executable is passed via the parameter
+/// `exe_path`. If the operation succeeds, the returned result will contain
+/// an iterator over the lines of the config file. If the operation fails, the
+/// result will contain an IO error.
+pub fn read_ini(
+    user_defined_config: &Option<String>,
+    exe_path: &Path,
+    win_console: bool,
+) -> Result<impl Iterator<Item = String>, Error> {
+    read_ini_lines(ini_path(user_defined_config, exe_path, win_console))
+}
+
+/// Returns the path of the product ini file read by `read_ini`. This is the user specified
+/// location, if `user_defined_config` is `Some`, or otherwise the location relative to
+/// the executable at `exe_path`.
+pub fn ini_path(
+    user_defined_config: &Option<String>,
+    exe_path: &Path,
+    win_console: bool,
+) -> PathBuf {
+    if let Some(user_ini) = user_defined_config {
+        PathBuf::from(user_ini)
+    } else {
+        exe_to_ini_path(exe_path, win_console)
+    }
+}
+
+/// Returns the path of the `IniLayer::User` ini file for the executable at `exe_path`.
+/// Returns `None` on Windows or if the user configuration directory is unknown.
+pub fn user_ini_path(exe_path: &Path, win_console: bool) -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        return None;
+    }
+    let (product, file_name) = product_file_name(exe_path, win_console)?;
+    Some(user_config_dir()?.join(product).join(file_name))
+}
+
+/// Returns the product name and ini file name, both derived from
+/// the ini file next to the executable at `exe_path`.
+fn product_file_name(exe_path: &Path, win_console: bool) -> Option<(PathBuf, PathBuf)> {
+    let default_ini = exe_to_ini_path(exe_path, win_console);
+    let file_name = PathBuf::from(default_ini.file_name()?);
+    let product = file_name.with_extension("");
+    Some((product, file_name))
+}
+
+/// The layer of the configuration chain an ini file belongs to. Layers are
+/// declared from lowest to highest precedence:
+/// * `Product`: the ini file next to the executable, or the file specified via `--launcher.ini`
+/// * `System`: `/etc/<product>/<product>.ini`, maintained by an administrator
+/// * `User`: `$XDG_CONFIG_HOME/<product>/<product>.ini` (defaulting to `~/.config`)
+///
+/// Launcher options of a layer override the options of all lower layers. The VM arguments
+/// of all layers are concatenated from lowest to highest precedence, so if a VM option is
+/// specified multiple times the JVM uses the value of the layer with the highest precedence.
+/// System and user layers are not supported on Windows.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum IniLayer {
+    Product,
+    System,
+    User,
+}
+
+/// A line read from an ini file, together with the path of the file. For lines
+/// spliced in via `--launcher.include` this is the path of the included file.
+/// Lines of a profile section hold the name of the profile.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IniLine {
+    pub value: String,
+    pub path: PathBuf,
+    pub profile: Option<String>,
+}
+
+/// The lines of one ini file in the configuration chain, with
+/// `--launcher.include` directives already resolved.
+#[derive(Debug)]
+pub struct IniFile {
+    pub layer: IniLayer,
+    pub path: PathBuf,
+    pub lines: Vec<IniLine>,
+
+    /// Names of all profile sections of the file
+    pub profiles: Vec<String>,
+}
+
+impl IniFile {
+    /// Returns the lines in front of the `-vmargs` line.
+    pub fn launcher_args(&self) -> impl Iterator<Item = &IniLine> {
+        self.lines.iter().take_while(|line| line.value != VMARGS)
+    }
+
+    /// Returns the lines following the `-vmargs` line.
+    pub fn vm_args(&self) -> impl Iterator<Item = &IniLine> {
+        self.lines
+            .iter()
+            .skip_while(|line| line.value != VMARGS)
+            .skip(1)
+    }
+}
+
+/// A line of the configuration chain, together with the file it was read from.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct IniSetting<'a> {
+    pub value: &'a str,
+    pub layer: IniLayer,
+    pub path: &'a Path,
+    pub profile: Option<&'a str>,
+}
+
+/// All ini files of the configuration chain that exist, ordered
+/// from lowest to highest precedence. See `IniLayer` for details.
+#[derive(Debug, Default)]
+pub struct IniChain {
+    files: Vec<IniFile>,
+}
+
+impl IniChain {
+    /// Returns the files of the chain, ordered from lowest to highest precedence.
+    pub fn files(&self) -> &[IniFile] {
+        &self.files
+    }
+
+    /// Returns the launcher options (lines before `-vmargs`) of all files, ordered from
+    /// lowest to highest precedence. Since parsers let later options win, the resulting
+    /// list can be parsed as is.
+    pub fn launcher_args(&self) -> Vec<IniSetting<'_>> {
+        self.settings(IniFile::launcher_args)
+    }
+
+    /// Splits the launcher options of all files (see `launcher_args`) into the values of
+    /// `--launcher.env` and the remaining options. Variables in these values are expanded
+    /// when the JVM is launched (see `UNEXPANDED_VALUE_OPTIONS`), so they are kept together
+    /// with the file they are read from. The values have the form `<name>=<value>`.
+    pub fn split_env_values(&self) -> (Vec<IniSetting<'_>>, Vec<IniSetting<'_>>) {
+        let mut env_values = Vec::new();
+        let mut other_args = Vec::new();
+        let mut settings = self.launcher_args().into_iter().peekable();
+        while let Some(setting) = settings.next() {
+            let inline_value = setting.value.strip_prefix(ENV).and_then(|v| v.strip_prefix('='));
+            if let Some(value) = inline_value {
+                env_values.push(IniSetting { value, ..setting });
+                continue;
+            }
+            // a value on the next line has to be read from the same file
+            let next_value = settings.next_if(|next| {
+                setting.value == ENV && next.path == setting.path && next.profile == setting.profile
+            });
+            match next_value {
+                Some(value) => env_values.push(value),
+                None => other_args.push(setting),
+            }
+        }
+        (env_values, other_args)
+    }
+
+    /// Returns the VM arguments (lines after `-vmargs`) of all files,
+    /// ordered from lowest to highest precedence.
+    pub fn vm_args(&self) -> Vec<IniSetting<'_>> {
+        self.settings(IniFile::vm_args)
+    }
+
+    /// Returns `true` if a file of the chain has a section for the profile `name`.
+    pub fn has_profile(&self, name: &str) -> bool {
+        self.files
+            .iter()
+            .any(|file| file.profiles.iter().any(|profile| profile == name))
+    }
+
+    /// Returns the line of the highest precedence layer specifying the
+    /// launcher option `name`. This is the line the effective value
+    /// of the option originates from.
+    pub fn origin_of(&self, name: &str) -> Option<IniSetting<'_>> {
+        self.launcher_args()
+            .into_iter()
+            .rev()
+            .find(|setting| setting.value == name)
+    }
+
+    fn settings<'a, I: Iterator<Item = &'a IniLine>>(
+        &'a self,
+        lines: impl Fn(&'a IniFile) -> I,
+    ) -> Vec<IniSetting<'a>> {
+        self.files
+            .iter()
+            .flat_map(|file| {
+                lines(file).map(move |line| IniSetting {
+                    value: &line.value,
+                    layer: file.layer,
+                    path: &line.path,
+                    profile: line.profile.as_deref(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Error reading the configuration chain, holding the
+/// path of the file the error occurred in.
+#[derive(Debug)]
+pub struct IniError {
+    pub path: PathBuf,
+    pub kind: IniErrorKind,
+}
+
+/// Categories of errors reading the configuration chain.
+#[derive(Debug)]
+pub enum IniErrorKind {
+    /// A variable in a line cannot be expanded
+    Variable(VariableError),
+
+    /// `--launcher.include` is the last line of a file, not followed by a path
+    IncludeMissingPath,
+
+    /// The file referenced by `--launcher.include` cannot be read
+    IncludeNotReadable(PathBuf, Error),
+
+    /// The file referenced by `--launcher.include` is already being read
+    IncludeCycle(PathBuf),
+
+    /// A conditional block is invalid
+    Condition(ConditionError),
+}
+
+impl fmt::Display for IniError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use IniErrorKind::*;
+        match &self.kind {
+            Variable(cause) => write!(f, "{}", cause)?,
+            IncludeMissingPath => write!(f, "{} is not followed by a file path", INCLUDE)?,
+            IncludeNotReadable(path, cause) => write!(
+                f,
+                "Included file {} cannot be read ({})",
+                path.display(),
+                cause
+            )?,
+            IncludeCycle(path) => write!(f, "File {} includes itself", path.display())?,
+            Condition(cause) => write!(f, "{}", cause)?,
+        }
+        write!(f, " in configuration file {}", self.path.display())
+    }
+}
+
+impl std::error::Error for IniError {}
+
+/// Returns `true` if `line` is one of the `UNEXPANDED_VALUE_OPTIONS` in the form
+/// `<option>=<value>`
+fn has_unexpanded_value(line: &str) -> bool {
+    UNEXPANDED_VALUE_OPTIONS.iter().any(|option| {
+        line.strip_prefix(option).is_some_and(|value| value.starts_with('='))
+    })
+}
+
+/// Reads the configuration chain of system, user and product ini files. The product ini
+/// is either the user specified one, if `user_defined_config` is `Some`, or the one located
+/// relative to the executable `exe_path`. Files that do not exist or cannot be read are
+/// skipped, so the resulting chain may be empty. Variables in the read lines are expanded
+/// and `--launcher.include` directives are resolved, see `resolve_lines`. If this fails,
+/// an `IniError` is returned. Of the profile sections only the one named `profile` is read.
+/// Conditional blocks are evaluated against `platform`.
+pub fn read_ini_chain(
+    user_defined_config: &Option<String>,
+    exe_path: &Path,
+    win_console: bool,
+    profile: Option<&str>,
+    platform: &Platform,
+) -> Result<IniChain, IniError> {
+    let product_ini = ini_path(user_defined_config, exe_path, win_console);
+    let mut candidates = vec![(IniLayer::Product, product_ini)];
+    if cfg!(not(target_os = "windows")) {
+        // The system and user files are named after the product ini next to the executable
+        if let Some((product, file_name)) = product_file_name(exe_path, win_console) {
+            let system_ini = Path::new(SYSTEM_CONFIG_DIR).join(product).join(file_name);
+            candidates.push((IniLayer::System, system_ini));
+        }
+        if let Some(user_ini) = user_ini_path(exe_path, win_console) {
+            candidates.push((IniLayer::User, user_ini));
+        }
+    }
+
+    let launcher_dir = exe_path.parent().unwrap_or_else(|| Path::new(""));
+    let mut files: Vec<IniFile> = Vec::new();
+    for (layer, path) in candidates {
+        // The same file may be referenced by multiple layers, e.g. via --launcher.ini
+        if files.iter().any(|file| file.path == path) {
+            continue;
+        }
+        files.extend(read_ini_file(layer, path, launcher_dir, profile, platform)?);
+    }
+    Ok(IniChain { files })
+}
+
+/// Reads the ini file at `path` as file of the given `layer` of the configuration chain,
+/// see `read_ini_chain`. Variables are expanded relative to the `launcher_dir`. Returns
+/// `None` if the file does not exist or cannot be read.
+pub fn read_ini_file(
+    layer: IniLayer,
+    path: PathBuf,
+    launcher_dir: &Path,
+    profile: Option<&str>,
+    platform: &Platform,
+) -> Result<Option<IniFile>, IniError> {
+    let lines_iter = match read_ini_lines(&path) {
+        Ok(lines_iter) => lines_iter,
+        Err(_) => return Ok(None),
+    };
+    let lines = filter_conditional_blocks(lines_iter, platform).map_err(|e| IniError {
+        path: path.clone(),
+        kind: IniErrorKind::Condition(e),
+    })?;
+    let split = split_profiles(lines.into_iter(), profile);
+    let mut include_stack = vec![canonical_or_self(&path)];
+    let base_lines = split.base.into_iter();
+    let mut sections = resolve_lines(
+        &path,
+        base_lines,
+        launcher_dir,
+        platform,
+        &mut include_stack,
+    )?;
+    if let (Some(name), Some(profile_lines)) = (profile, split.selected) {
+        let profile_lines = profile_lines.into_iter();
+        let profile_sections = resolve_lines(
+            &path,
+            profile_lines,
+            launcher_dir,
+            platform,
+            &mut include_stack,
+        )?;
+        sections.append_profile(profile_sections, name);
+    }
+    let lines = sections.into_lines(&path);
+    Ok(Some(IniFile {
+        layer,
+        path,
+        lines,
+        profiles: split.names,
+    }))
+}
+
+/// Returns the profile selected via `--launcher.profile`, given as `profile_option`,
+/// or otherwise via the environment variable `ECLIPSE_PROFILE`.
+pub fn select_profile(profile_option: Option<String>) -> Option<String> {
+    profile_option
+        .or_else(|| std::env::var(PROFILE_ENV).ok())
+        .filter(|profile| !profile.trim().is_empty())
+}
+
+/// Returns the name of the profile, if `line` starts a profile section.
+fn profile_section_name(line: &str) -> Option<&str> {
+    line.strip_prefix(PROFILE_SECTION_START)?
+        .strip_suffix(PROFILE_SECTION_END)
+        .map(str::trim)
+}
+
+/// Lines of an ini file, split by profile sections
+struct ProfileSplit {
+    /// Lines in front of the first profile section
+    base: Vec<String>,
+
+    /// Lines of the selected profile, if the file has a section for it
+    selected: Option<Vec<String>>,
+
+    /// Names of all profile sections
+    names: Vec<String>,
+}
+
+/// Splits `lines` at profile sections, keeping only the lines of the section
+/// named `profile`. If a profile has multiple sections, their lines are joined.
+fn split_profiles(lines: impl Iterator<Item = String>, profile: Option<&str>) -> ProfileSplit {
+    let mut split = ProfileSplit {
+        base: Vec::new(),
+        selected: None,
+        names: Vec::new(),
+    };
+    // `None` while in front of the first profile section
+    let mut current_profile: Option<String> = None;
+    for line in lines {
+        if let Some(name) = profile_section_name(&line) {
+            if !split.names.iter().any(|known| known == name) {
+                split.names.push(name.to_string());
+            }
+            if Some(name) == profile {
+                split.selected.get_or_insert_with(Vec::new);
+            }
+            current_profile = Some(name.to_string());
+            continue;
+        }
+        match &current_profile {
+            None => split.base.push(line),
+            Some(name) if Some(name.as_str()) == profile => {
+                split.selected.get_or_insert_with(Vec::new).push(line)
+            }
+            Some(_) => {}
+        }
+    }
+    split
+}
+
+/// Lines of an ini file, split at the `-vmargs` line
+#[derive(Default)]
+struct IniSections {
+    launcher_args: Vec<IniLine>,
+    vm_args: Option<Vec<IniLine>>,
+}
+
+impl IniSections {
+    /// Appends the launcher options and VM arguments of the profile section `name`.
+    fn append_profile(&mut self, profile: IniSections, name: &str) {
+        let with_profile = |lines: Vec<IniLine>| {
+            lines.into_iter().map(|line| IniLine {
+                profile: Some(name.to_string()),
+                ..line
+            })
+        };
+        self.launcher_args
+            .extend(with_profile(profile.launcher_args));
+        if let Some(vm_args) = profile.vm_args {
+            self.vm_args
+                .get_or_insert_with(Vec::new)
+                .extend(with_profile(vm_args));
+        }
+    }
+
+    /// Joins the sections, separated by a `-vmargs` line if VM args are present.
+    fn into_lines(self, path: &Path) -> Vec<IniLine> {
+        let mut lines = self.launcher_args;
+        if let Some(vm_args) = self.vm_args {
+            lines.push(IniLine {
+                value: VMARGS.to_string(),
+                path: path.to_path_buf(),
+                profile: None,
+            });
+            lines.extend(vm_args);
+        }
+        lines
+    }
+}
+
+/// Expands variables in the `lines` read from file `path` and splices in the lines of files
+/// referenced via `--launcher.include <path>`. The values of `UNEXPANDED_VALUE_OPTIONS`
+/// are kept as is. Relative include paths are resolved against
+/// the directory of the including file. The `-vmargs` boundary is respected:
+/// * If the include directive is located in front of `-vmargs`, the launcher options of the
+///   included file are spliced in place and its VM arguments are put in front of the VM
+///   arguments of the including file. So options of the including file win over included ones.
+/// * If the include directive is located after `-vmargs`, all lines of the included file are
+///   treated as VM arguments and are spliced in place.
+///
+/// Conditional blocks of included files are evaluated against `platform`. The
+/// `include_stack` holds the canonical paths of all files currently being read,
+/// to detect include cycles.
+fn resolve_lines(
+    path: &Path,
+    lines: impl Iterator<Item = String>,
+    launcher_dir: &Path,
+    platform: &Platform,
+    include_stack: &mut Vec<PathBuf>,
+) -> Result<IniSections, IniError> {
+    let ini_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let context = VariableContext {
+        launcher_dir,
+        ini_dir,
+    };
+    let to_ini_err = |kind: IniErrorKind| IniError {
+        path: path.to_path_buf(),
+        kind,
+    };
+    let expand = |line: &str| {
+        expand_variables(line, &context).map_err(|e| to_ini_err(IniErrorKind::Variable(e)))
+    };
+
+    let mut sections = IniSections::default();
+    // VM args of files included in front of -vmargs
+    let mut included_vm_args: Vec<IniLine> = Vec::new();
+    let mut lines = lines;
+    // whether the line is the value of one of the `UNEXPANDED_VALUE_OPTIONS`
+    let mut is_unexpanded_value = false;
+    while let Some(line) = lines.next() {
+        if is_unexpanded_value || (sections.vm_args.is_none() && has_unexpanded_value(&line)) {
+            is_unexpanded_value = false;
+            sections.launcher_args.push(IniLine {
+                value: line,
+                path: path.to_path_buf(),
+                profile: None,
+            });
+            continue;
+        }
+        let value = expand(&line)?;
+        is_unexpanded_value =
+            sections.vm_args.is_none() && UNEXPANDED_VALUE_OPTIONS.contains(&value.as_str());
+        if value == VMARGS && sections.vm_args.is_none() {
+            sections.vm_args = Some(Vec::new());
+            continue;
+        }
+        if value != INCLUDE {
+            let ini_line = IniLine {
+                value,
+                path: path.to_path_buf(),
+                profile: None,
+            };
+            match &mut sections.vm_args {
+                Some(vm_args) => vm_args.push(ini_line),
+                None => sections.launcher_args.push(ini_line),
+            }
+            continue;
+        }
+
+        // Include directive
+        let include_value = lines
+            .next()
+            .ok_or_else(|| to_ini_err(IniErrorKind::IncludeMissingPath))?;
+        let include_path = ini_dir.join(expand(&include_value)?);
+        let canonical_include = canonical_or_self(&include_path);
+        if include_stack.contains(&canonical_include) {
+            return Err(to_ini_err(IniErrorKind::IncludeCycle(include_path)));
+        }
+        let include_lines = read_ini_lines(&include_path)
+            .map_err(|e| to_ini_err(IniErrorKind::IncludeNotReadable(include_path.clone(), e)))?;
+        let include_lines =
+            filter_conditional_blocks(include_lines, platform).map_err(|e| IniError {
+                path: include_path.clone(),
+                kind: IniErrorKind::Condition(e),
+            })?;
+        include_stack.push(canonical_include);
+        let included = resolve_lines(
+            &include_path,
+            include_lines.into_iter(),
+            launcher_dir,
+            platform,
+            include_stack,
+        )?;
+        include_stack.pop();
+
+        match &mut sections.vm_args {
+            Some(vm_args) => {
+                vm_args.extend(included.launcher_args);
+                vm_args.extend(included.vm_args.unwrap_or_default());
+            }
+            None => {
+                sections.launcher_args.extend(included.launcher_args);
+                included_vm_args.extend(included.vm_args.unwrap_or_default());
+            }
+        }
+    }
+
+    if !included_vm_args.is_empty() {
+        included_vm_args.extend(sections.vm_args.unwrap_or_default());
+        sections.vm_args = Some(included_vm_args);
+    }
+    Ok(sections)
+}
+
+/// Returns the canonical form of `path`, or `path` itself if it cannot be canonicalized.
+fn canonical_or_self(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Directory holding the per-user configuration, as specified
+/// by the XDG Base Directory Specification
+fn user_config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+}
+
+/// Reads all lines from the file located on the given `ini_path`
+/// Skipping blank and commented lines (starting with '#'). If reading the file
+/// succeeds, the result will be an iterator over all lines, otherwise
+/// will return an IO error. See `IniDocument::values`. Lines that are
+/// not valid UTF-8 are reported on stderr and decoded lossily.
+pub fn read_ini_lines<P: AsRef<Path>>(ini_path: P) -> Result<impl Iterator<Item = String>, Error> {
+    let ini_path = ini_path.as_ref();
+    let doc = IniDocument::load(ini_path)?;
+    for line_number in doc.invalid_lines() {
+        eprintln!(
+            "Warning: Line {} of configuration file {} is not valid UTF-8",
+            line_number,
+            ini_path.display()
+        );
+    }
+    Ok(doc.into_values())
+}
+
+/// Removes a possible file extension off the given `exe_path`
+/// and adds the file extension `ini`. If the filename starts with
+/// `c` on windows, the prefix is removed. The prefix is used
+/// for launchers creating a console window on the win32 windowsing system.
+fn exe_to_ini_path(exe_path: &Path, win_console: bool) -> PathBuf {
+    let mut ini_path = exe_path.to_owned();
+    ini_path.set_extension("ini");
+
+    if cfg!(target_os = "macos") {
+        let adjusted = adjust_macos_ini_path(&ini_path);
+        if let Some(mac_path) = adjusted {
+            return mac_path;
+        }
+        // If the MacOS path cannot be determined
+        // the default location next to the launcher is returned
+    }
+
+    // We are the (win) console version, if the ini file does not exist, try
+    // removing the 'c' from the end of the program name
+    if win_console {
+        let adjusted_file_name_opt = adjust_console_file_name(&ini_path);
+        if let Some(adjusted_file_name) = adjusted_file_name_opt {
+            ini_path.set_file_name(adjusted_file_name);
+        }
+    }
+
+    ini_path
+}
+
+/// On MacOSX, the eclipse.ini is not a sibling of the executable.
+/// It is in `../Eclipse/<launcherName>.ini` relatively to the executable.
+/// This method assumes the given `ini_path` references to the ini file
+/// next to the launcher executable. This method adjusts the path and
+/// returns a `PathBuf` pointing to the correct location.
+/// If the location cannot be determined, an empty `Option` is returned.
+fn adjust_macos_ini_path(ini_path: &Path) -> Option<PathBuf> {
+    let parent = ini_path.parent()?.parent()?;
+    let file_name = ini_path.file_name()?;
+    let mut result = parent.to_path_buf();
+    result.push("Eclipse");
+    result.push(file_name);
+    Some(result)
+}
+
+/// Detects if the launcher name is a for a Windows executable
+/// starting a console, and returns an adjusted ini file name if so.
+/// The criterion for detection is, that the file base name (without
+/// file extension) ends with the character 'c'. This trailing character will
+/// be stripped off the complete file name.
+fn adjust_console_file_name(ini_path: &Path) -> Option<String> {
+    let file_stem = ini_path.file_stem()?.to_str()?;
+    let file_ext = ini_path.extension()?.to_str()?;
+    let extended = true;
+    // get last unicode character cluster
+    let graphemes = file_stem.graphemes(extended);
+    let (index, last_char) = graphemes.enumerate().last()?;
+    if last_char == "c" {
+        // get all elements except for last character
+        // and add file extension back
+        let mut stripped = file_stem
+            .graphemes(extended)
+            .take(index)
+            .collect::<String>();
+        stripped.push('.');
+        stripped.push_str(file_ext);
+        Some(stripped)
+    } else {
+        // Nothing to change
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        read_ini_chain, split_profiles, IniChain, IniErrorKind, IniFile, IniLayer, IniLine,
+    };
+    use crate::compile_params::Platform;
+    use std::path::{Path, PathBuf};
+
+    const PLATFORM: Platform<'static> = Platform {
+        os: "linux",
+        arch: "x86_64",
+        ws: "gtk",
+    };
+
+    fn ini_file(layer: IniLayer, path: &str, lines: &[&str]) -> IniFile {
+        IniFile {
+            layer,
+            path: PathBuf::from(path),
+            lines: lines.iter().map(|s| ini_line(s, path)).collect(),
+            profiles: Vec::new(),
+        }
+    }
+
+    fn ini_line(value: &str, path: &str) -> IniLine {
+        IniLine {
+            value: value.to_string(),
+            path: PathBuf::from(path),
+            profile: None,
+        }
+    }
+
+    fn test_chain() -> IniChain {
+        IniChain {
+            files: vec![
+                ini_file(
+                    IniLayer::Product,
+                    "eclipse.ini",
+                    &["-name", "Product", "-vmargs", "-Xmx1g"],
+                ),
+                ini_file(
+                    IniLayer::System,
+                    "/etc/eclipse/eclipse.ini",
+                    &["--launcher.library", "lib"],
+                ),
+                ini_file(
+                    IniLayer::User,
+                    "user.ini",
+                    &["-name", "User", "-vmargs", "-Xmx4g"],
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_launcher_args_ordered_by_precedence() {
+        let chain = test_chain();
+        let args: Vec<&str> = chain.launcher_args().iter().map(|s| s.value).collect();
+        assert_eq!(
+            args,
+            [
+                "-name",
+                "Product",
+                "--launcher.library",
+                "lib",
+                "-name",
+                "User"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_vm_args_concatenated() {
+        let chain = test_chain();
+        let vm_args = chain.vm_args();
+        let values: Vec<&str> = vm_args.iter().map(|s| s.value).collect();
+        assert_eq!(values, ["-Xmx1g", "-Xmx4g"]);
+        assert_eq!(vm_args[1].layer, IniLayer::User);
+    }
+
+    #[test]
+    fn test_origin_of_highest_layer() {
+        let chain = test_chain();
+        let name_origin = chain.origin_of("-name").map(|s| s.layer);
+        assert_eq!(name_origin, Some(IniLayer::User));
+        let lib_origin = chain.origin_of("--launcher.library").map(|s| s.layer);
+        assert_eq!(lib_origin, Some(IniLayer::System));
+        assert!(chain.origin_of("-Xmx4g").is_none());
+    }
+
+    /// An empty directory in the temp folder, which is removed when dropped
+    struct TestDir(PathBuf);
+
+    impl std::ops::Deref for TestDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Creates an empty directory in the temp folder, unique for the given `name`
+    fn test_dir(name: &str) -> TestDir {
+        let dir =
+            std::env::temp_dir().join(format!("eclipse_ini_test_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        TestDir(dir)
+    }
+
+    fn write_file(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_env_values_not_expanded() {
+        let dir = test_dir("env_values");
+        let ini = write_file(
+            &dir,
+            "eclipse.ini",
+            "--launcher.env\nA=1\n--launcher.env\nB=${env:A}\n--launcher.unsetEnv\n\
+             ${env:ECLIPSE_INI_TEST_UNSET}\n--launcher.env=C=${env:B}\n-name\n${launcher.dir}\n",
+        );
+        let ini_str = Some(ini.to_string_lossy().to_string());
+        let exe = Path::new("/opt/eclipse/eclipse");
+        let chain = read_ini_chain(&ini_str, exe, false, None, &PLATFORM).unwrap();
+        let args: Vec<&str> = chain.files()[0]
+            .launcher_args()
+            .map(|l| l.value.as_str())
+            .collect();
+        let expected = [
+            "--launcher.env",
+            "A=1",
+            "--launcher.env",
+            "B=${env:A}",
+            "--launcher.unsetEnv",
+            "${env:ECLIPSE_INI_TEST_UNSET}",
+            "--launcher.env=C=${env:B}",
+            "-name",
+            "/opt/eclipse",
+        ];
+        assert_eq!(args, expected);
+    }
+
+    #[test]
+    fn test_split_env_values() {
+        let dir = test_dir("split_env");
+        std::fs::create_dir_all(dir.join("conf")).unwrap();
+        write_file(&dir, "conf/team.ini", "--launcher.env\nTEAM=${ini.dir}\n");
+        let ini = write_file(
+            &dir,
+            "eclipse.ini",
+            "--launcher.include\nconf/team.ini\n--launcher.env=A=1\n-name\nTest\n",
+        );
+        let ini_str = Some(ini.to_string_lossy().to_string());
+        let chain = read_ini_chain(&ini_str, &dir.join("eclipse"), false, None, &PLATFORM).unwrap();
+        let (env_values, other_args) = chain.split_env_values();
+        let values: Vec<(&str, &Path)> = env_values.iter().map(|s| (s.value, s.path)).collect();
+        let team_ini = dir.join("conf").join("team.ini");
+        assert_eq!(values, [("TEAM=${ini.dir}", team_ini.as_path()), ("A=1", ini.as_path())]);
+        let args: Vec<&str> = other_args.iter().map(|s| s.value).collect();
+        assert_eq!(args, ["-name", "Test"]);
+    }
+
+    #[test]
+    fn test_include_respects_vmargs_boundary() {
+        let dir = test_dir("include");
+        write_file(&dir, "team.ini", "-name\nTeam\n-vmargs\n-Xmx1g\n");
+        let ini = write_file(
+            &dir,
+            "eclipse.ini",
+            "--launcher.include\nteam.ini\n-showsplash\n-vmargs\n-Xmx2g\n",
+        );
+        let ini_str = Some(ini.to_string_lossy().to_string());
+        let chain = read_ini_chain(&ini_str, &dir.join("eclipse"), false, None, &PLATFORM).unwrap();
+        let product = &chain.files()[0];
+        let args: Vec<&str> = product.launcher_args().map(|l| l.value.as_str()).collect();
+        assert_eq!(args, ["-name", "Team", "-showsplash"]);
+        let vm_args: Vec<&str> = product.vm_args().map(|l| l.value.as_str()).collect();
+        assert_eq!(vm_args, ["-Xmx1g", "-Xmx2g"]);
+        assert_eq!(product.lines[0].path, dir.join("team.ini"));
+    }
+
+    #[test]
+    fn test_include_in_vmargs_section() {
+        let dir = test_dir("include_vmargs");
+        write_file(
+            &dir,
+            "opens.txt",
+            "--add-opens\njava.base/java.lang=ALL-UNNAMED\n",
+        );
+        let ini = write_file(
+            &dir,
+            "eclipse.ini",
+            "-vmargs\n-Xmx2g\n--launcher.include\nopens.txt\n",
+        );
+        let ini_str = Some(ini.to_string_lossy().to_string());
+        let chain = read_ini_chain(&ini_str, &dir.join("eclipse"), false, None, &PLATFORM).unwrap();
+        let vm_args: Vec<&str> = chain.files()[0]
+            .vm_args()
+            .map(|l| l.value.as_str())
+            .collect();
+        assert_eq!(
+            vm_args,
+            ["-Xmx2g", "--add-opens", "java.base/java.lang=ALL-UNNAMED"]
+        );
+    }
+
+    #[test]
+    fn test_include_cycle_detected() {
+        let dir = test_dir("include_cycle");
+        write_file(&dir, "a.ini", "--launcher.include\nb.ini\n");
+        write_file(&dir, "b.ini", "--launcher.include\na.ini\n");
+        let ini_str = Some(dir.join("a.ini").to_string_lossy().to_string());
+        let err =
+            read_ini_chain(&ini_str, &dir.join("eclipse"), false, None, &PLATFORM).unwrap_err();
+        assert!(matches!(err.kind, IniErrorKind::IncludeCycle(_)));
+    }
+
+    #[test]
+    fn test_split_profiles() {
+        let content = "-name\nBase\n[profile:low-memory]\n-vmargs\n-Xmx512m\n[profile:profiling]\n-vmargs\n-agentpath:p.so\n[profile:low-memory]\n-Xss256k\n";
+        let lines = content.lines().map(str::to_string);
+        let split = split_profiles(lines, Some("low-memory"));
+        assert_eq!(split.base, ["-name", "Base"]);
+        assert_eq!(split.selected.unwrap(), ["-vmargs", "-Xmx512m", "-Xss256k"]);
+        assert_eq!(split.names, ["low-memory", "profiling"]);
+    }
+
+    #[test]
+    fn test_profile_overrides_base() {
+        let dir = test_dir("profile");
+        let ini = write_file(
+            &dir,
+            "eclipse.ini",
+            "-name\nBase\n-vmargs\n-Xmx2g\n[profile:profiling]\n-name\nProfiling\n-vmargs\n-Xmx4g\n",
+        );
+        let ini_str = Some(ini.to_string_lossy().to_string());
+        let exe = dir.join("eclipse");
+        let chain = read_ini_chain(&ini_str, &exe, false, Some("profiling"), &PLATFORM).unwrap();
+        assert!(chain.has_profile("profiling"));
+        let origin = chain.origin_of("-name").unwrap();
+        assert_eq!(origin.profile, Some("profiling"));
+        let vm_args: Vec<&str> = chain.vm_args().iter().map(|s| s.value).collect();
+        assert_eq!(vm_args, ["-Xmx2g", "-Xmx4g"]);
+
+        let chain = read_ini_chain(&ini_str, &exe, false, None, &PLATFORM).unwrap();
+        let args: Vec<&str> = chain.launcher_args().iter().map(|s| s.value).collect();
+        assert_eq!(args, ["-name", "Base"]);
+        let vm_args: Vec<&str> = chain.vm_args().iter().map(|s| s.value).collect();
+        assert_eq!(vm_args, ["-Xmx2g"]);
+    }
+
+    #[test]
+    fn test_conditional_blocks_in_chain() {
+        let dir = test_dir("conditions");
+        write_file(
+            &dir,
+            "arm.ini",
+            "[if arch=aarch64]\n-vmargs\n-Xss2m\n[endif]\n",
+        );
+        let ini = write_file(
+            &dir,
+            "eclipse.ini",
+            "[if arch=x86_64]\n-vm\n/opt/jdk-x64\n[endif]\n--launcher.include\narm.ini\n",
+        );
+        let ini_str = Some(ini.to_string_lossy().to_string());
+        let chain = read_ini_chain(&ini_str, &dir.join("eclipse"), false, None, &PLATFORM).unwrap();
+        let args: Vec<&str> = chain.launcher_args().iter().map(|s| s.value).collect();
+        assert_eq!(args, ["-vm", "/opt/jdk-x64"]);
+        assert!(chain.vm_args().is_empty());
+    }
+}
@@ -0,0 +1,274 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides the function `expand_variables`, which replaces variables
+//! in lines of launcher ini files. The following variables are supported:
+//! * `${env:NAME}` is replaced by the value of environment variable `NAME`.
+//!   With `${env:NAME:-default}` the text `default` is used if the variable is
+//!   not set or empty. The default text may contain variables itself.
+//! * `${user.home}` and `@user.home` are replaced by the home directory of the user.
+//! * `${launcher.dir}` and `@launcher.dir` are replaced by the directory of the launcher executable.
+//! * `${ini.dir}` is replaced by the directory of the ini file the line was read from.
+//!
+//! Other variables in the form `${name}` are left unchanged, since they may be interpreted
+//! by the Java application.
+
+use std::fmt;
+use std::path::Path;
+
+const VAR_START: &str = "${";
+const VAR_END: char = '}';
+const ENV_PREFIX: &str = "env:";
+const DEFAULT_SEPARATOR: &str = ":-";
+const USER_HOME: &str = "user.home";
+const LAUNCHER_DIR: &str = "launcher.dir";
+const INI_DIR: &str = "ini.dir";
+const AT_PREFIX: char = '@';
+const AT_VARIABLES: &[&str] = &[USER_HOME, LAUNCHER_DIR];
+
+/// Provides the values of the location variables for `expand_variables`.
+#[derive(Debug, Clone, Copy)]
+pub struct VariableContext<'a> {
+    /// Directory of the launcher executable
+    pub launcher_dir: &'a Path,
+
+    /// Directory of the ini file the expanded line is read from
+    pub ini_dir: &'a Path,
+}
+
+/// Errors occurring while expanding variables.
+#[derive(Debug, Eq, PartialEq)]
+pub enum VariableError {
+    /// An environment variable is not set and no default value was given.
+    /// Holds the complete variable expression, e.g. `${env:FOO}`.
+    Unresolved(String),
+
+    /// A variable expression is missing the closing `}`.
+    Unterminated(String),
+}
+
+impl fmt::Display for VariableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VariableError::Unresolved(var) => write!(f, "Variable {} cannot be resolved", var),
+            VariableError::Unterminated(var) => write!(f, "Variable {} is missing a closing '}}'", var),
+        }
+    }
+}
+
+impl std::error::Error for VariableError {}
+
+/// Replaces all supported variables in `line` by their values. See module documentation
+/// for the supported variables. Returns an error if an environment variable without
+/// default value cannot be resolved.
+pub fn expand_variables(line: &str, context: &VariableContext) -> Result<String, VariableError> {
+    expand_variables_with_env(line, context, &|name| std::env::var(name).ok())
+}
+
+/// Like `expand_variables`, but the values of `${env:NAME}` variables and the home
+/// directory are looked up via `env` instead of the environment of this process.
+pub fn expand_variables_with_env(
+    line: &str,
+    context: &VariableContext,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> Result<String, VariableError> {
+    // Both syntaxes are expanded in one pass, so values are never expanded again
+    let mut result = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find(['$', AT_PREFIX]) {
+        result.push_str(&rest[..start]);
+        let var_expr = &rest[start..];
+        let var_len = if var_expr.starts_with(VAR_START) {
+            let end = find_var_end(var_expr)
+                .ok_or_else(|| VariableError::Unterminated(var_expr.to_string()))?;
+            let var_name = &var_expr[VAR_START.len()..end];
+            match resolve(var_name, context, env)? {
+                Some(value) => result.push_str(&value),
+                // Not one of our variables, keep it for the Java application
+                None => result.push_str(&var_expr[..=end]),
+            }
+            end + 1
+        } else if let Some(var_name) = at_variable_name(var_expr) {
+            let var_len = AT_PREFIX.len_utf8() + var_name.len();
+            let unresolved = |_| VariableError::Unresolved(var_expr[..var_len].to_string());
+            let value = resolve(var_name, context, env).map_err(unresolved)?;
+            result.push_str(&value.unwrap_or_default());
+            var_len
+        } else {
+            // A single '$' or '@' which does not start a variable
+            result.push_str(&var_expr[..1]);
+            1
+        };
+        rest = &var_expr[var_len..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Returns the index of the `}` closing the variable expression starting
+/// at the beginning of `var_expr`. Nested variable expressions are skipped.
+fn find_var_end(var_expr: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = var_expr.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if c == '$' && chars.peek().map(|(_, next)| *next) == Some('{') {
+            chars.next();
+            depth += 1;
+        } else if c == VAR_END {
+            depth -= 1;
+            if depth == 0 {
+                return Some(index);
+            }
+        }
+    }
+    None
+}
+
+/// Resolves the variable `name` (without surrounding `${` and `}`). Returns `None`
+/// if the name does not denote a variable supported by this module.
+fn resolve(
+    name: &str,
+    context: &VariableContext,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> Result<Option<String>, VariableError> {
+    if let Some(env_expr) = name.strip_prefix(ENV_PREFIX) {
+        let (env_name, default) = match env_expr.find(DEFAULT_SEPARATOR) {
+            Some(index) => (&env_expr[..index], Some(&env_expr[index + DEFAULT_SEPARATOR.len()..])),
+            None => (env_expr, None),
+        };
+        let env_value = env(env_name).filter(|value| !value.is_empty());
+        return match (env_value, default) {
+            (Some(value), _) => Ok(Some(value)),
+            (None, Some(default)) => expand_variables_with_env(default, context, env).map(Some),
+            (None, None) => Err(unresolved(name)),
+        };
+    }
+    let value = match name {
+        USER_HOME => user_home(env).ok_or_else(|| unresolved(name))?,
+        LAUNCHER_DIR => context.launcher_dir.to_string_lossy().to_string(),
+        INI_DIR => context.ini_dir.to_string_lossy().to_string(),
+        _ => return Ok(None),
+    };
+    Ok(Some(value))
+}
+
+fn unresolved(name: &str) -> VariableError {
+    VariableError::Unresolved(format!("{}{}{}", VAR_START, name, VAR_END))
+}
+
+/// Returns the name of the Eclipse style `@user.home` or `@launcher.dir` variable at the
+/// beginning of `var_expr`, as long as the name is not directly followed by further name
+/// characters.
+fn at_variable_name(var_expr: &str) -> Option<&'static str> {
+    let name_start = var_expr.strip_prefix(AT_PREFIX)?;
+    AT_VARIABLES.iter().copied().find(|name| {
+        name_start.strip_prefix(name).is_some_and(|after| {
+            !matches!(after.chars().next(), Some(c) if c.is_alphanumeric() || c == '.' || c == '_')
+        })
+    })
+}
+
+fn user_home(env: &dyn Fn(&str) -> Option<String>) -> Option<String> {
+    let home_var = if cfg!(target_os = "windows") {
+        "USERPROFILE"
+    } else {
+        "HOME"
+    };
+    env(home_var).filter(|home| !home.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{expand_variables, expand_variables_with_env, VariableContext, VariableError};
+    use std::path::Path;
+
+    fn context() -> VariableContext<'static> {
+        VariableContext {
+            launcher_dir: Path::new("/opt/eclipse"),
+            ini_dir: Path::new("/etc/eclipse"),
+        }
+    }
+
+    #[test]
+    fn test_expand_no_variables() {
+        let result = expand_variables("-Xmx1g", &context());
+        assert_eq!(result, Ok("-Xmx1g".to_string()));
+    }
+
+    #[test]
+    fn test_expand_dirs() {
+        let result = expand_variables("${ini.dir}/plugins:@launcher.dir/jre", &context());
+        assert_eq!(result, Ok("/etc/eclipse/plugins:/opt/eclipse/jre".to_string()));
+    }
+
+    #[test]
+    fn test_expand_env() {
+        let env = |name: &str| match name {
+            "ECLIPSE_STARTER_TEST_VAR" => Some("foo".to_string()),
+            _ => None,
+        };
+        let line = "-Dbar=${env:ECLIPSE_STARTER_TEST_VAR}";
+        let result = expand_variables_with_env(line, &context(), &env);
+        assert_eq!(result, Ok("-Dbar=foo".to_string()));
+    }
+
+    #[test]
+    fn test_expand_values_not_expanded_again() {
+        let env = |name: &str| match name {
+            "ECLIPSE_STARTER_TEST_VAR" => Some("mail@launcher.dir".to_string()),
+            _ => None,
+        };
+        let line = "-Dfoo=${env:ECLIPSE_STARTER_TEST_VAR}";
+        let result = expand_variables_with_env(line, &context(), &env);
+        assert_eq!(result, Ok("-Dfoo=mail@launcher.dir".to_string()));
+    }
+
+    #[test]
+    fn test_expand_missing_home() {
+        let no_env = |_: &str| None;
+        let result = expand_variables_with_env("${user.home}/x", &context(), &no_env);
+        let expected = VariableError::Unresolved("${user.home}".to_string());
+        assert_eq!(result, Err(expected));
+        let result = expand_variables_with_env("@user.home/x", &context(), &no_env);
+        let expected = VariableError::Unresolved("@user.home".to_string());
+        assert_eq!(result, Err(expected));
+    }
+
+    #[test]
+    fn test_expand_env_default() {
+        let line = "-Dbar=${env:ECLIPSE_STARTER_UNSET_VAR:-${ini.dir}/x}";
+        let result = expand_variables(line, &context());
+        assert_eq!(result, Ok("-Dbar=/etc/eclipse/x".to_string()));
+    }
+
+    #[test]
+    fn test_expand_env_unresolved() {
+        let result = expand_variables("${env:ECLIPSE_STARTER_UNSET_VAR}", &context());
+        let expected = VariableError::Unresolved("${env:ECLIPSE_STARTER_UNSET_VAR}".to_string());
+        assert_eq!(result, Err(expected));
+    }
+
+    #[test]
+    fn test_expand_unterminated() {
+        let result = expand_variables("-Dfoo=${ini.dir", &context());
+        let expected = VariableError::Unterminated("${ini.dir".to_string());
+        assert_eq!(result, Err(expected));
+    }
+
+    #[test]
+    fn test_expand_unknown_kept() {
+        let result = expand_variables("-Dfoo=${osgi.dir}@launcher.directory", &context());
+        assert_eq!(result, Ok("-Dfoo=${osgi.dir}@launcher.directory".to_string()));
+    }
+}
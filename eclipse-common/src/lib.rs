@@ -17,11 +17,14 @@
 //! `eclipse-launcher` and `eclipse-library` projects.
 
 pub mod arg_parser;
+pub mod compile_params;
 pub mod exe_util;
 pub mod ini_reader;
+pub mod ini_variables;
 pub mod messagebox;
 pub mod name_util;
 pub mod native_str;
+pub mod option_registry;
 pub mod option_util;
 pub mod path_util;
 pub mod eclipse_params_flags;
@@ -13,7 +13,7 @@
  *******************************************************************************/
 
 use gtk::prelude::*;
-use gtk::{Window, GtkWindowExt,MessageDialog, ButtonsType, DialogFlags, MessageType};
+use gtk::{Window, GtkWindowExt,MessageDialog, ButtonsType, DialogFlags, MessageType, ResponseType};
 
 ///  Display a Message
 /// 
@@ -25,6 +25,25 @@ pub fn display_message(message: &str, title: &str) -> Result<(), String> {
     message_dialog(message, title)
 }
 
+/// Asks the user the yes/no question given by `message`.
+/// Returns `true` if the user answered "Yes".
+pub fn ask_question(message: &str, title: &str) -> Result<bool, String> {
+    let _gtk = gtk::init().map_err(|_| "Initialization of GTK failed.")?;
+    let dialog = MessageDialog::new(None::<&Window>,
+                       DialogFlags::DESTROY_WITH_PARENT,
+                       MessageType::Question,
+                       ButtonsType::YesNo,
+                       message);
+    dialog.set_title(title);
+    let response = dialog.run();
+    // the program continues running, so the dialog has to be closed explicitly
+    dialog.destroy();
+    while gtk::events_pending() {
+        gtk::main_iteration();
+    }
+    Ok(response == ResponseType::Yes)
+}
+
 fn message_dialog(message: &str, title: &str) -> Result<(), String> {
     let _gtk = gtk::init().map_err(|_| "Initialization of GTK failed.")?;
     let dialog = MessageDialog::new(None::<&Window>,
@@ -34,4 +34,23 @@ pub fn display_message(message: &str, title: &str) -> Result<(), String> {
         alert.runModal();
     }
     Ok(())
- }
\ No newline at end of file
+ }
+
+/// Response of `runModal` if the first button was chosen
+const NS_ALERT_FIRST_BUTTON_RETURN: usize = 1000;
+
+/// Asks the user the yes/no question given by `message`.
+/// Returns `true` if the user answered "Yes".
+pub fn ask_question(message: &str, title: &str) -> Result<bool, String> {
+    let response = unsafe {
+        let alert = NSAlert::alloc(nil).init().autorelease();
+        alert.setAlertStyle(NSAlertStyle::informational);
+        alert.setMessageText(NSString::alloc(nil).init_str(title));
+        alert.setInformativeText(NSString::alloc(nil).init_str(message));
+        alert.addButton(NSString::alloc(nil).init_str("Yes"));
+        alert.addButton(NSString::alloc(nil).init_str("No"));
+        // runModal returns an NSModalResponse integer
+        alert.runModal() as usize
+    };
+    Ok(response == NS_ALERT_FIRST_BUTTON_RETURN)
+}
\ No newline at end of file
@@ -14,7 +14,8 @@
 
 //! This module provides the function `display_message` which can be used
 //! to display an error message to the user using a windowing system native
-//! to the OS (cocoa on MacOS, win32 on Windows, GTK on Linux).
+//! to the OS (cocoa on MacOS, win32 on Windows, GTK on Linux), and the function
+//! `ask_question` which asks the user a yes/no question.
 
 #[cfg_attr(target_os = "macos", path = "macos/mod.rs")]
 #[cfg_attr(target_os = "linux", path = "gtk.rs")]
@@ -22,4 +23,4 @@
 // TODO support gtk on windows/macos via feature flag?
 mod os;
 
-pub use os::display_message;
+pub use os::{ask_question, display_message};
@@ -15,7 +15,7 @@
 use crate::native_str::{to_native_str, NativeString};
 use std::io::Error;
 use std::ptr::null_mut;
-use winapi::um::winuser::{MessageBoxW, MB_OK};
+use winapi::um::winuser::{MessageBoxW, IDYES, MB_ICONQUESTION, MB_OK, MB_YESNO};
 
 ///  Display a Message
 ///
@@ -29,6 +29,19 @@ pub fn display_message(message: &str, title: &str) -> Result<(), String> {
     message_box(msg_ptr, title_ptr).map_err(|err| format!("{}", err))
 }
 
+/// Asks the user the yes/no question given by `message`.
+/// Returns `true` if the user answered "Yes".
+pub fn ask_question(message: &str, title: &str) -> Result<bool, String> {
+    let (_msg_container, msg_ptr) = to_native_str(message);
+    let (_title_container, title_ptr) = to_native_str(title);
+    let ret = unsafe { MessageBoxW(null_mut(), msg_ptr, title_ptr, MB_YESNO | MB_ICONQUESTION) };
+    if ret == 0 {
+        Err(format!("{}", Error::last_os_error()))
+    } else {
+        Ok(ret == IDYES)
+    }
+}
+
 fn message_box(msg: NativeString, caption: NativeString) -> Result<(), Error> {
     let ret = unsafe { MessageBoxW(null_mut(), msg, caption, MB_OK) };
     if ret == 0 {
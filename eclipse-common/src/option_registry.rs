@@ -0,0 +1,579 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides `OPTIONS`, the declarative registry of all options handled by
+//! the launcher executable and its companion library. The argument parsers of both are
+//! built from this registry via `register_options`, and `--launcher.help` prints it as
+//! a table via `help_table`.
+//!
+//! Both sides register all options, even the ones consumed by the other side. This way
+//! both agree on which arguments are values of options, and no option handled by the
+//! native launcher is passed on to the Java application.
+
+use crate::arg_parser::{
+    FlagId, ListId, ListParseStyle, OptionId, OptionValue, OptionalOptionId, Parser,
+    RepeatedOptionId, RuleId, RuleKind, TypedOptionId, TypedRepeatedOptionId,
+};
+use crate::eclipse_params_flags::*;
+use std::collections::HashMap;
+
+/// The part of the launcher an option is consumed by
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum Consumer {
+    /// The launcher executable
+    Launcher,
+
+    /// The companion library starting the JVM
+    Library,
+
+    /// Both, the launcher executable and the companion library
+    Both,
+}
+
+impl Consumer {
+    fn describe(self) -> &'static str {
+        match self {
+            Consumer::Launcher => "launcher",
+            Consumer::Library => "library",
+            Consumer::Both => "both",
+        }
+    }
+}
+
+/// Declarative description of an option
+#[derive(Debug)]
+pub struct OptionSpec {
+    pub name: &'static str,
+    pub kind: RuleKind,
+
+    /// Placeholder for the value in the help text, empty for flags
+    pub value: &'static str,
+
+    /// Description of the behavior if the option is not set
+    pub default: Option<&'static str>,
+    pub help: &'static str,
+    pub consumer: Consumer,
+}
+
+/// All options handled by the launcher executable and the companion library
+pub static OPTIONS: &[OptionSpec] = &[
+    OptionSpec {
+        name: CONSOLE,
+        kind: RuleKind::OptionalOption,
+        value: "[[host:]port]",
+        default: None,
+        help: "Equivalent to setting osgi.console",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: CONSOLELOG,
+        kind: RuleKind::Flag,
+        value: "",
+        default: None,
+        help: "Equivalent to setting eclipse.consoleLog to true",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: DEBUG,
+        kind: RuleKind::OptionalOption,
+        value: "[options file]",
+        default: None,
+        help: "Prints launcher debug output, equivalent to setting osgi.debug",
+        consumer: Consumer::Both,
+    },
+    OptionSpec {
+        name: OS,
+        kind: RuleKind::Option,
+        value: "<os>",
+        default: None,
+        help: "Equivalent to setting osgi.os",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: OSARCH,
+        kind: RuleKind::Option,
+        value: "<arch>",
+        default: None,
+        help: "Equivalent to setting osgi.arch",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: NOSPLASH,
+        kind: RuleKind::Flag,
+        value: "",
+        default: None,
+        help: "Does not show the splash screen",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: SHOWSPLASH,
+        kind: RuleKind::OptionalOption,
+        value: "[bitmap]",
+        default: None,
+        help: "Shows the splash screen, optionally with the given bitmap",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: STARTUP,
+        kind: RuleKind::Option,
+        value: "<jar>",
+        default: Some("newest org.eclipse.equinox.launcher in plugins"),
+        help: "The jar used to start up Eclipse",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: VM,
+        kind: RuleKind::Option,
+        value: "<path>",
+        default: Some("searched"),
+        help: "The Java VM: a bin directory, java executable, VM library or .ee file",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: WS,
+        kind: RuleKind::Option,
+        value: "<window system>",
+        default: None,
+        help: "Equivalent to setting osgi.ws",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: NAME,
+        kind: RuleKind::Option,
+        value: "<name>",
+        default: Some("name of the executable"),
+        help: "The name shown in the task bar and in error dialogs",
+        consumer: Consumer::Both,
+    },
+    OptionSpec {
+        name: PROTECT,
+        kind: RuleKind::Option,
+        value: "<mode>",
+        default: None,
+        help: "With 'root', the launcher refuses to run as root user",
+        consumer: Consumer::Launcher,
+    },
+    OptionSpec {
+        name: OPENFILE,
+        kind: RuleKind::List(ListParseStyle::UntilDashPrefix),
+        value: "<file>...",
+        default: None,
+        help: "Files to open in the application",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: DEFAULTACTION,
+        kind: RuleKind::Option,
+        value: "<action>",
+        default: None,
+        help: "Action for arguments without option, 'openFile' opens them",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: TIMEOUT,
+        kind: RuleKind::Option,
+        value: "<duration>",
+        default: None,
+        help: "Time to wait for a running instance to open files",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: LIBRARY,
+        kind: RuleKind::Option,
+        value: "<path>",
+        default: Some("newest launcher fragment in plugins"),
+        help: "The companion shared library of the launcher",
+        consumer: Consumer::Both,
+    },
+    OptionSpec {
+        name: SUPRESSERRORS,
+        kind: RuleKind::Flag,
+        value: "",
+        default: None,
+        help: "Prints errors to the console instead of showing dialogs",
+        consumer: Consumer::Both,
+    },
+    OptionSpec {
+        name: INI,
+        kind: RuleKind::Option,
+        value: "<path>",
+        default: Some("<executable>.ini"),
+        help: "The launcher ini file to use",
+        consumer: Consumer::Both,
+    },
+    OptionSpec {
+        name: INCLUDE,
+        kind: RuleKind::Option,
+        value: "<path>",
+        default: None,
+        help: "Reads further lines from the given file (ini files only)",
+        consumer: Consumer::Launcher,
+    },
+    OptionSpec {
+        name: APPEND_VMARGS,
+        kind: RuleKind::Flag,
+        value: "",
+        default: None,
+        help: "Appends command line VM arguments to the ones of the ini file",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: OVERRIDE_VMARGS,
+        kind: RuleKind::Flag,
+        value: "",
+        default: None,
+        help: "Command line VM arguments replace the ones of the ini file",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: SECOND_THREAD,
+        kind: RuleKind::Flag,
+        value: "",
+        default: None,
+        help: "Starts the VM in a second thread (macOS only)",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: PERM_GEN,
+        kind: RuleKind::Option,
+        value: "<size>",
+        default: None,
+        help: "Value of -XX:MaxPermSize for VMs older than Java 8",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: GTK_VERSION,
+        kind: RuleKind::Option,
+        value: "2|3",
+        default: None,
+        help: "The major version of GTK to use (Linux only)",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: PROFILE,
+        kind: RuleKind::Option,
+        value: "<name>",
+        default: Some("$ECLIPSE_PROFILE"),
+        help: "Selects the [profile:<name>] sections of the ini files",
+        consumer: Consumer::Both,
+    },
+    OptionSpec {
+        name: RESTART_LIMIT,
+        kind: RuleKind::Option,
+        value: "<count>",
+        default: Some("5"),
+        help: "Maximum number of JVM restarts within the restart window",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: RESTART_WINDOW,
+        kind: RuleKind::Option,
+        value: "<duration>",
+        default: Some("60s"),
+        help: "Time window in which JVM restarts are counted",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: OOM_RETRY,
+        kind: RuleKind::Flag,
+        value: "",
+        default: None,
+        help: "Restarts the JVM with a larger -Xmx after an OutOfMemoryError",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: STARTUP_TIMEOUT,
+        kind: RuleKind::Option,
+        value: "<seconds>",
+        default: None,
+        help: "Requests a thread dump if the JVM did not start within this time",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: RLIMIT,
+        kind: RuleKind::RepeatedOption,
+        value: "<resource>=<soft>[:<hard>]",
+        default: Some("nofile=hard"),
+        help: "Sets a resource limit of the JVM (not on Windows), may be repeated",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: NICE,
+        kind: RuleKind::Option,
+        value: "<-20..19>",
+        default: None,
+        help: "Sets the nice value of the JVM process (not on Windows)",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: IO_PRIORITY,
+        kind: RuleKind::Option,
+        value: "<class>[:<level>]",
+        default: None,
+        help: "Sets the I/O priority of the JVM: realtime, best-effort or idle (Linux only)",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: CPU_AFFINITY,
+        kind: RuleKind::Option,
+        value: "<cpus>",
+        default: None,
+        help: "Restricts the JVM to the given CPUs, e.g. 0-3,6 (Linux only)",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: HEAP_PERCENT,
+        kind: RuleKind::Option,
+        value: "<1..100>",
+        default: None,
+        help: "Sets -Xmx to this percentage of the available (container) memory, if not set",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: ENV,
+        kind: RuleKind::RepeatedOption,
+        value: "<name>=<value>",
+        default: None,
+        help: "Sets an environment variable of the JVM, may be repeated",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: UNSET_ENV,
+        kind: RuleKind::RepeatedOption,
+        value: "<name>",
+        default: None,
+        help: "Removes an environment variable of the JVM, may be repeated",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: CLEAN_ENV,
+        kind: RuleKind::Flag,
+        value: "",
+        default: None,
+        help: "Removes all environment variables of the JVM, except essential and kept ones",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: KEEP_ENV,
+        kind: RuleKind::RepeatedOption,
+        value: "<name>[*]",
+        default: None,
+        help: "Keeps an environment variable (or prefix) with --launcher.cleanEnv, may be repeated",
+        consumer: Consumer::Library,
+    },
+    OptionSpec {
+        name: STRICT,
+        kind: RuleKind::Flag,
+        value: "",
+        default: None,
+        help: "Treats invalid launcher arguments as errors instead of warnings",
+        consumer: Consumer::Launcher,
+    },
+    OptionSpec {
+        name: CONFIG,
+        kind: RuleKind::List(ListParseStyle::AllRemaining),
+        value: "<command>",
+        default: None,
+        help: "Queries or modifies the launcher ini file, commands: get, set, unset, list",
+        consumer: Consumer::Launcher,
+    },
+    OptionSpec {
+        name: HELP,
+        kind: RuleKind::Flag,
+        value: "",
+        default: None,
+        help: "Prints this table of options",
+        consumer: Consumer::Launcher,
+    },
+    OptionSpec {
+        name: VMARGS,
+        kind: RuleKind::List(ListParseStyle::AllRemaining),
+        value: "<arg>...",
+        default: None,
+        help: "All following arguments are passed to the Java VM",
+        consumer: Consumer::Both,
+    },
+];
+
+/// Returns the registry entry of the option with the given `name`
+pub fn option_spec(name: &str) -> Option<&'static OptionSpec> {
+    OPTIONS.iter().find(|spec| spec.name == name)
+}
+
+/// The ids of the parsing rules added by `register_options`, looked up by option name.
+/// Looking up a name not registered with the requested kind of rule is a programming
+/// error and panics.
+pub struct RegisteredOptions {
+    ids: HashMap<&'static str, RuleId>,
+}
+
+impl RegisteredOptions {
+    fn rule(&self, name: &str) -> RuleId {
+        match self.ids.get(name) {
+            Some(id) => *id,
+            None => panic!("Option {} is not registered", name),
+        }
+    }
+
+    pub fn option(&self, name: &'static str) -> OptionId {
+        match self.rule(name) {
+            RuleId::Option(id) => id,
+            other => panic!("Option {} is registered as {:?}", name, other),
+        }
+    }
+
+    pub fn typed_option<T: OptionValue>(&self, name: &'static str) -> TypedOptionId<T> {
+        self.option(name).typed(name)
+    }
+
+    pub fn repeated_option(&self, name: &'static str) -> RepeatedOptionId {
+        match self.rule(name) {
+            RuleId::RepeatedOption(id) => id,
+            other => panic!("Option {} is registered as {:?}", name, other),
+        }
+    }
+
+    pub fn typed_repeated_option<T: OptionValue>(
+        &self,
+        name: &'static str,
+    ) -> TypedRepeatedOptionId<T> {
+        self.repeated_option(name).typed(name)
+    }
+
+    pub fn optional_option(&self, name: &'static str) -> OptionalOptionId {
+        match self.rule(name) {
+            RuleId::OptionalOption(id) => id,
+            other => panic!("Option {} is registered as {:?}", name, other),
+        }
+    }
+
+    pub fn flag(&self, name: &'static str) -> FlagId {
+        match self.rule(name) {
+            RuleId::Flag(id) => id,
+            other => panic!("Option {} is registered as {:?}", name, other),
+        }
+    }
+
+    pub fn list(&self, name: &'static str) -> ListId {
+        match self.rule(name) {
+            RuleId::List(id) => id,
+            other => panic!("Option {} is registered as {:?}", name, other),
+        }
+    }
+}
+
+/// Adds the parsing rules of all options in `OPTIONS` to `parser`.
+pub fn register_options(parser: &mut Parser) -> RegisteredOptions {
+    let ids = OPTIONS
+        .iter()
+        .map(|spec| (spec.name, parser.add_rule(spec.name, spec.kind)))
+        .collect();
+    RegisteredOptions { ids }
+}
+
+/// Formats all options in `OPTIONS` as a table with the columns option,
+/// consumer and description.
+pub fn help_table() -> String {
+    let usages: Vec<String> = OPTIONS.iter().map(usage).collect();
+    let width = usages.iter().map(|usage| usage.len()).max().unwrap_or(0);
+    let mut table = format!("{:width$}  {:8}  {}\n", "Option", "Used by", "Description");
+    for (spec, usage) in OPTIONS.iter().zip(usages) {
+        let line = format!(
+            "{:width$}  {:8}  {}",
+            usage,
+            spec.consumer.describe(),
+            spec.help
+        );
+        table.push_str(&line);
+        if let Some(default) = spec.default {
+            table.push_str(&format!(" (default: {})", default));
+        }
+        table.push('\n');
+    }
+    table
+}
+
+fn usage(spec: &OptionSpec) -> String {
+    if spec.value.is_empty() {
+        spec.name.to_string()
+    } else {
+        format!("{} {}", spec.name, spec.value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{help_table, option_spec, register_options, OPTIONS};
+    use crate::arg_parser::{OptionalParam, Parser};
+    use crate::eclipse_params_flags::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_option_names_unique() {
+        let names: HashSet<&str> = OPTIONS.iter().map(|spec| spec.name).collect();
+        assert_eq!(names.len(), OPTIONS.len());
+    }
+
+    #[test]
+    fn test_register_options() {
+        let args = [
+            "-debug", "-vm", "/opt/jdk", "-data", "ws", "-vmargs", "-Xmx1g",
+        ];
+        let mut parser = Parser::new();
+        let ids = register_options(&mut parser);
+        let mut parse_result = parser.parse(args.iter().copied());
+        let debug = parse_result.take_optional_option(ids.optional_option(DEBUG));
+        assert_eq!(debug, OptionalParam::SetNoVal);
+        assert_eq!(
+            parse_result.take_option(ids.option(VM)).unwrap(),
+            "/opt/jdk"
+        );
+        let vm_args = parse_result.take_list(ids.list(VMARGS)).unwrap();
+        assert_eq!(vm_args, vec!["-Xmx1g".to_string()]);
+        assert_eq!(parse_result.get_remainder(), vec!["-data", "ws"]);
+    }
+
+    #[test]
+    fn test_clean_env_takes_no_value() {
+        let args = [
+            "--launcher.keepEnv", "MAVEN_OPTS", "--launcher.cleanEnv", "-data", "ws",
+        ];
+        let mut parser = Parser::new();
+        let ids = register_options(&mut parser);
+        let mut parse_result = parser.parse(args.iter().copied());
+        assert!(parse_result.take_flag(ids.flag(CLEAN_ENV)));
+        let keep_env = parse_result.take_repeated_option(ids.repeated_option(KEEP_ENV));
+        assert_eq!(keep_env, vec!["MAVEN_OPTS"]);
+        assert_eq!(parse_result.get_remainder(), vec!["-data", "ws"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_registered_wrong_kind() {
+        let ids = register_options(&mut Parser::new());
+        ids.flag(VM);
+    }
+
+    #[test]
+    fn test_help_table() {
+        let table = help_table();
+        assert_eq!(table.lines().count(), OPTIONS.len() + 1);
+        let vm_line = table
+            .lines()
+            .find(|line| line.starts_with("-vm <path>"))
+            .unwrap();
+        assert!(vm_line.ends_with("(default: searched)"));
+        assert!(option_spec(TIMEOUT).is_some());
+    }
+}
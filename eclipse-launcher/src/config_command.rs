@@ -0,0 +1,291 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module implements the `--launcher.config` subcommands, which query and
+//! modify the launcher ini file without starting a JVM:
+//! * `get <key>` prints the value of a launcher option or the VM argument with the given key
+//! * `set <key> [value]` sets a launcher option, flag or VM argument
+//! * `unset <key>` removes a launcher option, flag or VM argument
+//! * `list` prints all settings of the ini file
+//!
+//! Keys naming a launcher option of the option registry (e.g. `-vm` or
+//! `--launcher.appendVmargs`) are located in front of `-vmargs`, all other keys
+//! are treated as VM arguments.
+//! VM arguments are matched via `vm_arg_key`, so `set -Xmx 4g` replaces an existing
+//! `-Xmx1g` and `set -Dfoo bar` replaces `-Dfoo=baz`.
+
+use crate::errors::LauncherError;
+use eclipse_common::arg_parser::RuleKind;
+use eclipse_common::eclipse_params_flags::{CONFIG, LAUNCHER_PREFIX, VMARGS};
+use eclipse_common::ini_reader::{vm_arg_key, IniDocument};
+use eclipse_common::option_registry::option_spec;
+use std::io::ErrorKind;
+use std::path::Path;
+
+static MSG_USAGE: &str = "Usage: --launcher.config get|set|unset|list [<key> [<value>]]";
+
+/// Memory size options, where the value is appended to the option directly
+const SIZE_OPTIONS: &[&str] = &["-Xmx", "-Xms", "-Xss", "-Xmn"];
+
+enum ConfigCommand<'a> {
+    Get(ConfigKey<'a>),
+    Set(ConfigKey<'a>, Option<&'a str>),
+    Unset(ConfigKey<'a>),
+    List,
+}
+
+/// The kind of setting a key given to a subcommand refers to
+#[derive(Clone, Copy)]
+enum ConfigKey<'a> {
+    Flag(&'a str),
+    Option(&'a str),
+    VmArg(&'a str),
+}
+
+impl<'a> ConfigKey<'a> {
+    fn new(key: &'a str) -> ConfigKey<'a> {
+        match option_spec(key).map(|spec| spec.kind) {
+            Some(RuleKind::Flag) => ConfigKey::Flag(key),
+            Some(RuleKind::List(_)) | None if !key.starts_with(LAUNCHER_PREFIX) => {
+                ConfigKey::VmArg(key)
+            }
+            _ => ConfigKey::Option(key),
+        }
+    }
+}
+
+/// Looks for `--launcher.config` in the launcher arguments `args`, which do not include the
+/// program location. Arguments following `-vmargs` are not searched. Returns the index of the
+/// option and the first subcommand argument, if given in the form `--launcher.config=<command>`.
+pub fn find_config_command(args: &[String]) -> Option<(usize, Option<&str>)> {
+    args.iter()
+        .take_while(|arg| *arg != VMARGS)
+        .enumerate()
+        .find_map(|(index, arg)| match arg.strip_prefix(CONFIG)? {
+            "" => Some((index, None)),
+            rest => Some((index, Some(rest.strip_prefix('=')?))),
+        })
+}
+
+/// Runs the `--launcher.config` subcommand given by `args`, which are the arguments following
+/// `--launcher.config`, on the ini file at `ini_path`. If the ini file is not writable, the
+/// user is asked to write the setting to the per-user ini file at `user_ini_path` instead.
+pub fn run_config_command(
+    args: &[&str],
+    ini_path: &Path,
+    user_ini_path: Option<&Path>,
+) -> Result<(), LauncherError> {
+    let command = parse_command(args)?;
+    let mut doc = match IniDocument::load(ini_path) {
+        Ok(doc) => doc,
+        // setting a value creates a new file
+        Err(ref e)
+            if e.kind() == ErrorKind::NotFound && matches!(command, ConfigCommand::Set(..)) =>
+        {
+            IniDocument::default()
+        }
+        Err(e) => return Err(read_error(ini_path, &e)),
+    };
+
+    match command {
+        ConfigCommand::List => {
+            doc.values().for_each(|value| println!("{}", value));
+            Ok(())
+        }
+        ConfigCommand::Get(key) => {
+            let value = get_value(&doc, key).ok_or_else(|| {
+                format!(
+                    "{} is not set in configuration file {}",
+                    key_str(key),
+                    ini_path.display()
+                )
+            })?;
+            println!("{}", value);
+            Ok(())
+        }
+        ConfigCommand::Unset(key) => {
+            if unset_value(&mut doc, key) {
+                doc.save(ini_path).map_err(|e| write_error(ini_path, &e))?;
+            }
+            Ok(())
+        }
+        ConfigCommand::Set(key, value) => {
+            set_value(&mut doc, key, value)?;
+            match doc.save(ini_path) {
+                Err(ref e) if e.kind() == ErrorKind::PermissionDenied => {
+                    let user_ini_path = user_ini_path.filter(|user_path| *user_path != ini_path);
+                    match user_ini_path {
+                        Some(user_path) if confirm_user_override(ini_path, user_path) => {
+                            set_user_value(user_path, key, value)
+                        }
+                        _ => Err(write_error(ini_path, e)),
+                    }
+                }
+                result => result.map_err(|e| write_error(ini_path, &e)),
+            }
+        }
+    }
+}
+
+fn parse_command<'a>(args: &[&'a str]) -> Result<ConfigCommand<'a>, LauncherError> {
+    let command = match args {
+        ["list"] => ConfigCommand::List,
+        ["get", key] => ConfigCommand::Get(ConfigKey::new(key)),
+        ["unset", key] => ConfigCommand::Unset(ConfigKey::new(key)),
+        ["set", key] => ConfigCommand::Set(ConfigKey::new(key), None),
+        ["set", key, value] => ConfigCommand::Set(ConfigKey::new(key), Some(value)),
+        _ => return Err(MSG_USAGE.into()),
+    };
+    Ok(command)
+}
+
+fn key_str(key: ConfigKey<'_>) -> &str {
+    match key {
+        ConfigKey::Flag(name) | ConfigKey::Option(name) | ConfigKey::VmArg(name) => name,
+    }
+}
+
+fn get_value<'d>(doc: &'d IniDocument, key: ConfigKey) -> Option<&'d str> {
+    match key {
+        ConfigKey::Flag(name) if doc.has_launcher_flag(name) => Some("true"),
+        ConfigKey::Flag(_) => None,
+        ConfigKey::Option(name) => doc.launcher_option(name),
+        ConfigKey::VmArg(name) => doc.vm_arg(name),
+    }
+}
+
+fn set_value(
+    doc: &mut IniDocument,
+    key: ConfigKey,
+    value: Option<&str>,
+) -> Result<(), LauncherError> {
+    match (key, value) {
+        (ConfigKey::Flag(name), None) => doc.set_launcher_flag(name),
+        (ConfigKey::Flag(name), Some(_)) => {
+            return Err(format!("{} does not take a value", name).into());
+        }
+        (ConfigKey::Option(name), Some(value)) => doc.set_launcher_option(name, value),
+        (ConfigKey::Option(name), None) => {
+            return Err(format!("{} requires a value", name).into());
+        }
+        (ConfigKey::VmArg(name), value) => doc.set_vm_arg(&vm_arg_with_value(name, value)),
+    }
+    Ok(())
+}
+
+/// Removes the setting denoted by `key`, returns `true` if the document was modified.
+fn unset_value(doc: &mut IniDocument, key: ConfigKey) -> bool {
+    match key {
+        ConfigKey::Flag(name) => doc.remove_launcher_flag(name),
+        ConfigKey::Option(name) => doc.remove_launcher_option(name),
+        ConfigKey::VmArg(name) => doc.remove_vm_arg(name),
+    }
+}
+
+/// Builds a VM argument from a `key` and an optional `value`. Memory size options
+/// are directly followed by the value (`-Xmx4g`), other options are separated
+/// from the value by `=` (`-Dfoo=bar`).
+fn vm_arg_with_value(key: &str, value: Option<&str>) -> String {
+    match value {
+        Some(value) if SIZE_OPTIONS.contains(&vm_arg_key(key).as_str()) => {
+            format!("{}{}", key, value)
+        }
+        Some(value) => format!("{}={}", key, value),
+        None => key.to_string(),
+    }
+}
+
+/// Sets the value in the per-user ini file, which overrides the product ini file.
+fn set_user_value(
+    user_ini_path: &Path,
+    key: ConfigKey,
+    value: Option<&str>,
+) -> Result<(), LauncherError> {
+    let mut doc = match IniDocument::load(user_ini_path) {
+        Ok(doc) => doc,
+        Err(ref e) if e.kind() == ErrorKind::NotFound => IniDocument::default(),
+        Err(e) => return Err(read_error(user_ini_path, &e)),
+    };
+    set_value(&mut doc, key, value)?;
+    if let Some(dir) = user_ini_path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| write_error(user_ini_path, &e))?;
+    }
+    doc.save(user_ini_path)
+        .map_err(|e| write_error(user_ini_path, &e))
+}
+
+/// Asks the user on the console whether to write to the per-user ini file instead.
+fn confirm_user_override(ini_path: &Path, user_ini_path: &Path) -> bool {
+    eprint!(
+        "Configuration file {} is not writable. Write the setting to {} instead? [y/N] ",
+        ini_path.display(),
+        user_ini_path.display()
+    );
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok() && answer.trim().eq_ignore_ascii_case("y")
+}
+
+fn read_error(path: &Path, e: &std::io::Error) -> LauncherError {
+    format!(
+        "Configuration file {} cannot be read: {}",
+        path.display(),
+        e
+    )
+    .into()
+}
+
+fn write_error(path: &Path, e: &std::io::Error) -> LauncherError {
+    format!(
+        "Configuration file {} cannot be written: {}",
+        path.display(),
+        e
+    )
+    .into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_config_command, set_value, vm_arg_with_value, ConfigKey};
+    use eclipse_common::ini_reader::IniDocument;
+
+    #[test]
+    fn test_vm_arg_with_value() {
+        assert_eq!(vm_arg_with_value("-Xmx", Some("4g")), "-Xmx4g");
+        assert_eq!(vm_arg_with_value("-Dfoo", Some("bar")), "-Dfoo=bar");
+        assert_eq!(vm_arg_with_value("-XX:+UseG1GC", None), "-XX:+UseG1GC");
+    }
+
+    #[test]
+    fn test_set_values() {
+        let mut doc = IniDocument::parse("-vmargs\n-Xmx1g\n");
+        set_value(&mut doc, ConfigKey::new("-Xmx"), Some("4g")).unwrap();
+        set_value(&mut doc, ConfigKey::new("-vm"), Some("/opt/jdk/bin")).unwrap();
+        set_value(&mut doc, ConfigKey::new("--launcher.appendVmargs"), None).unwrap();
+        assert!(set_value(&mut doc, ConfigKey::new("-name"), None).is_err());
+        let expected = "-vm\n/opt/jdk/bin\n--launcher.appendVmargs\n-vmargs\n-Xmx4g\n";
+        assert_eq!(doc.to_string(), expected);
+    }
+
+    #[test]
+    fn test_find_config_command() {
+        let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let args_list = args(&["--launcher.config", "list"]);
+        assert_eq!(find_config_command(&args_list), Some((0, None)));
+        let args_eq = args(&["-data", "ws", "--launcher.config=get", "-vm"]);
+        assert_eq!(find_config_command(&args_eq), Some((2, Some("get"))));
+        let args_vm = args(&["-vmargs", "--launcher.config", "list"]);
+        assert_eq!(find_config_command(&args_vm), None);
+        assert_eq!(find_config_command(&args(&["--launcher.configX"])), None);
+    }
+}
@@ -27,7 +27,7 @@ mod os;
 mod common;
 
 use eclipse_common::path_util::*;
-use crate::compile_params::*;
+use eclipse_common::compile_params::*;
 use crate::errors::LauncherError;
 use dlopen::symbor::Library;
 use os::EclipseLauncherOs;
@@ -25,17 +25,20 @@
     windows_subsystem = "windows"
 )]
 
-mod compile_params;
+mod config_command;
 mod errors;
 mod exe_util;
 mod launcher_lib;
 mod params;
 
+use config_command::{find_config_command, run_config_command};
 use eclipse_common::arg_parser::*;
+use eclipse_common::compile_params::Platform;
 use eclipse_common::eclipse_params_flags::*;
 use eclipse_common::ini_reader::*;
 use eclipse_common::messagebox::display_message;
 use eclipse_common::name_util::get_default_official_name;
+use eclipse_common::option_registry::{help_table, register_options};
 use eclipse_common::option_util::opt_str;
 use eclipse_common::path_util::strip_unc_prefix;
 use errors::LauncherError;
@@ -55,6 +58,7 @@ static MSG_LIB_PATH_CONVERSION_ERR: &str = "Converting path name of companion li
 static MSG_EXE_PATH_CONVERSION_ERR: &str = "Converting path name of launcher failed.";
 static MSG_ROOT_ERR: &str =
     "executable launcher is configured to not start with administrative privileges.";
+static MSG_STRICT_ERR: &str = "Invalid launcher arguments:";
 
 static ROOT: &str = "root";
 
@@ -87,21 +91,73 @@ fn main() {
 fn fallible_main(params: &mut EclipseLauncherParams) -> Result<(), LauncherError> {
     let command_line_args: Vec<String> = std::env::args().collect();
     let mut ini_file_args = Vec::<String>::new();
+    // arguments following --launcher.config form a subcommand, not launcher arguments
+    let config_command = find_config_command(&command_line_args[1..]);
+    let launcher_args_end = config_command.map_or(command_line_args.len(), |(index, _)| index + 1);
     // parse arguments without program location
-    parse_arguments(params, command_line_args.iter().map(String::as_str).skip(1));
+    let launcher_args = command_line_args[1..launcher_args_end].iter();
+    let diagnostics = parse_arguments(params, launcher_args.map(String::as_str));
+    let mut problems: Vec<String> = diagnostics
+        .iter()
+        .map(|diagnostic| format!("{} on the command line", diagnostic))
+        .collect();
 
     // Determine the full pathname of this program.
     let exe_path = get_exe_path().map_err(|_| MSG_EXE_LOCATION_NOT_FOUND)?;
     let win_console = cfg!(all(target_os = "windows", feature = "win_console"));
-    // read ini, only set params not already defined by program arguments
-    if let Ok(ini_file_lines) = read_ini(&params.launcher_ini, &exe_path, win_console) {
-        // we strip vmargs off (since the original launcher had this behavior,
-        // see eclipseMain.c main calling parseArgs with useVMargs = 0)
-        let ini_lines_no_vmargs = ini_file_lines.take_while(|s| s != VMARGS);
-        // store ini lines in vector for later usage
-        ini_file_args.extend(ini_lines_no_vmargs);
-        parse_arguments(params, ini_file_args.iter().map(String::as_str));
+    if let Some((_, command)) = config_command {
+        // run the subcommand without starting a JVM, report errors on the console
+        params.suppress_errors = true;
+        let config_args: Vec<&str> = command
+            .into_iter()
+            .chain(command_line_args[launcher_args_end + 1..].iter().map(String::as_str))
+            .collect();
+        let product_ini = ini_path(&params.launcher_ini, &exe_path, win_console);
+        let user_ini = user_ini_path(&exe_path, win_console);
+        return run_config_command(&config_args, &product_ini, user_ini.as_deref());
+    }
+    if params.help {
+        print!("{}", help_table());
+        return Ok(());
+    }
+    // read ini files, only set params not already defined by program arguments
+    params.profile = select_profile(params.profile.take());
+    let profile = params.profile.as_deref();
+    // conditional blocks in ini files can only be selected via -os, -arch and -ws
+    // on the command line, since the ini files are not read yet
+    let platform = Platform::with_overrides(
+        params.os.as_deref(),
+        params.arch.as_deref(),
+        params.ws.as_deref(),
+    );
+    let ini_chain = read_ini_chain(
+        &params.launcher_ini,
+        &exe_path,
+        win_console,
+        profile,
+        &platform,
+    )
+    .map_err(|e| e.to_string())?;
+    if let Some(profile) = profile.filter(|profile| !ini_chain.has_profile(profile)) {
+        problems.push(format!(
+            "Profile '{}' is not defined in the configuration files",
+            profile
+        ));
+    }
+    if params.debug {
+        print_ini_chain(&ini_chain);
     }
+    // we strip vmargs off (since the original launcher had this behavior,
+    // see eclipseMain.c main calling parseArgs with useVMargs = 0).
+    // The library reads --launcher.env values itself, they are expanded relative to their file
+    let (_, ini_args_no_env) = ini_chain.split_env_values();
+    let ini_lines_no_vmargs = ini_args_no_env
+        .into_iter()
+        .map(|setting| setting.value.to_string());
+    // store ini lines in vector for later usage
+    ini_file_args.extend(ini_lines_no_vmargs);
+    problems.extend(parse_ini_arguments(params, &ini_chain));
+    report_problems(params, &problems)?;
 
     // get default name if not yet set
     if params.name.is_none() {
@@ -123,6 +179,77 @@ fn fallible_main(params: &mut EclipseLauncherParams) -> Result<(), LauncherError
     load_lib_and_run(&params, &command_line_args, &ini_file_args, &exe_path)
 }
 
+/// Prints the ini files of the configuration chain, from
+/// lowest to highest precedence, to stderr.
+fn print_ini_chain(ini_chain: &IniChain) {
+    for ini_file in ini_chain.files() {
+        eprintln!(
+            "Launcher configuration ({:?}): {}",
+            ini_file.layer,
+            ini_file.path.display()
+        );
+    }
+}
+
+/// Parses the launcher options of the configuration chain and sets values in `params`,
+/// if they were not set already. Consecutive lines read from the same file (or profile
+/// section of a file) are parsed separately, starting with the lines of highest precedence.
+/// This way duplicate options are only reported within a file, while overriding options
+/// of other files and profiles is fine. Returns the parse problems, together with the
+/// file they occurred in.
+fn parse_ini_arguments(params: &mut EclipseLauncherParams, ini_chain: &IniChain) -> Vec<String> {
+    let settings = ini_chain.launcher_args();
+    let mut file_segments: Vec<&[IniSetting]> = Vec::new();
+    let mut segment_start = 0;
+    for index in 1..=settings.len() {
+        let segment_first = &settings[segment_start];
+        let is_segment_end = index == settings.len()
+            || settings[index].path != segment_first.path
+            || settings[index].profile != segment_first.profile;
+        if is_segment_end {
+            file_segments.push(&settings[segment_start..index]);
+            segment_start = index;
+        }
+    }
+
+    let mut problems = Vec::new();
+    for segment in file_segments.into_iter().rev() {
+        let diagnostics = parse_arguments(params, segment.iter().map(|setting| setting.value));
+        let segment_problems = diagnostics.iter().map(|diagnostic| {
+            let path = segment[0].path.display();
+            match segment[0].profile {
+                Some(profile) => format!(
+                    "{} in profile '{}' of configuration file {}",
+                    diagnostic, profile, path
+                ),
+                None => format!("{} in configuration file {}", diagnostic, path),
+            }
+        });
+        // keep problems in order of the configuration chain
+        problems.splice(0..0, segment_problems);
+    }
+    problems
+}
+
+/// Prints the parse `problems` as warnings to stderr. If the launcher
+/// runs in strict mode, an error listing the problems is returned instead.
+fn report_problems(
+    params: &EclipseLauncherParams,
+    problems: &[String],
+) -> Result<(), LauncherError> {
+    if problems.is_empty() {
+        return Ok(());
+    }
+    if params.strict {
+        let msg = format!("{}\n{}", MSG_STRICT_ERR, problems.join("\n"));
+        return Err(LauncherError::GeneralError(msg));
+    }
+    for problem in problems {
+        eprintln!("Warning: {}", problem);
+    }
+    Ok(())
+}
+
 /// Detects the location of the companion shared library library,
 /// loads it, and calls `setInitialArgs` and `run` on the library.
 fn load_lib_and_run(
@@ -181,36 +308,52 @@ fn merge_parameters<'a>(
 }
 
 /// Reads arguments from `args` and sets values in `params`, if they were not
-/// set already.
+/// set already. Returns the problems detected while parsing.
 fn parse_arguments<'a, 'b>(
     params: &'a mut EclipseLauncherParams,
     args: impl Iterator<Item = &'b str>,
-) {
+) -> Vec<ParseDiagnostic> {
     let mut parser: Parser = Parser::new();
 
-    // Define parameters to parse
-    let name = parser.add_option(NAME);
-    let eclipse_library = parser.add_option(LIBRARY);
-    let protect = parser.add_option(PROTECT);
-    let suppress_errors = parser.add_flag(SUPRESSERRORS);
-    let launcher_ini = parser.add_option(INI);
-    let vm_args = parser.add_list(VMARGS, ListParseStyle::AllRemaining);
+    // Define parameters to parse, options consumed by the companion
+    // library are registered as well to detect problems early
+    let ids = register_options(&mut parser);
+    parser.check_unknown_prefix(LAUNCHER_PREFIX, &[]);
 
     let mut parse_result = parser.parse(args);
 
     // Extract parsed parameters and set in `params` if they were found and not already set
-    set_if_none(&mut params.name, parse_result.take_option(name));
+    set_if_none(&mut params.name, parse_result.take_option(ids.option(NAME)));
     set_if_none(
         &mut params.eclipse_library,
-        parse_result.take_option(eclipse_library),
+        parse_result.take_option(ids.option(LIBRARY)),
+    );
+    params.suppress_errors |= parse_result.take_flag(ids.flag(SUPRESSERRORS));
+    params.debug |= parse_result
+        .take_optional_option(ids.optional_option(DEBUG))
+        .is_set();
+    params.strict |= parse_result.take_flag(ids.flag(STRICT));
+    params.help |= parse_result.take_flag(ids.flag(HELP));
+    set_if_none(
+        &mut params.profile,
+        parse_result.take_option(ids.option(PROFILE)),
+    );
+    set_if_none(&mut params.os, parse_result.take_option(ids.option(OS)));
+    set_if_none(&mut params.arch, parse_result.take_option(ids.option(OSARCH)));
+    set_if_none(&mut params.ws, parse_result.take_option(ids.option(WS)));
+    set_if_none(
+        &mut params.protect,
+        parse_result.take_option(ids.option(PROTECT)),
     );
-    params.suppress_errors |= parse_result.take_flag(suppress_errors);
-    set_if_none(&mut params.protect, parse_result.take_option(protect));
     set_if_none(
         &mut params.launcher_ini,
-        parse_result.take_option(launcher_ini),
+        parse_result.take_option(ids.option(INI)),
+    );
+    set_if_none(
+        &mut params.vm_args,
+        parse_result.take_list(ids.list(VMARGS)),
     );
-    set_if_none(&mut params.vm_args, parse_result.take_list(vm_args));
+    parse_result.diagnostics().to_vec()
 }
 
 /// If `target` is `None` and `from` is `Some(t)`, this
@@ -18,6 +18,13 @@ pub struct EclipseLauncherParams {
     pub name: Option<String>,
     pub eclipse_library: Option<String>,
     pub suppress_errors: bool,
+    pub debug: bool,
+    pub strict: bool,
+    pub help: bool,
+    pub profile: Option<String>,
+    pub os: Option<String>,
+    pub arch: Option<String>,
+    pub ws: Option<String>,
     pub protect: Option<String>,
     pub launcher_ini: Option<String>,
     pub vm_args: Option<Vec<String>>,
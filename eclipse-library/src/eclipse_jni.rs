@@ -14,6 +14,7 @@
  *******************************************************************************/
 
 use crate::shared_mem::{crete_shared_mem_ref, SharedMemRef, MAX_SHARED_LENGTH};
+use crate::vm_launch::mark_startup_complete;
 use jni::objects::{JObject, JString};
 use jni::sys::{jlong, jstring};
 use jni::JNIEnv;
@@ -58,6 +59,7 @@ pub extern "system" fn set_launcher_info(
     launcher: JString,
     name: JString,
 ) {
+    mark_startup_complete();
 }
 
 /// org_eclipse_equinox_launcher_JNIBridge#_update_splash
@@ -84,7 +86,9 @@ pub extern "system" fn show_splash(env: JNIEnv, obj: JObject, s: JString) {}
 /// Signature: ()V
 #[no_mangle]
 #[jni_mangle("org.eclipse.equinox.launcher.JNIBridge")]
-pub extern "system" fn takedown_splash(env: JNIEnv, object: JObject) {}
+pub extern "system" fn takedown_splash(env: JNIEnv, object: JObject) {
+    mark_startup_complete();
+}
 
 /// org_eclipse_equinox_launcher_JNIBridge#_get_os_recommended_folder
 /// Signature: ()Ljava/lang/String
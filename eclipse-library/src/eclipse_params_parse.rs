@@ -14,39 +14,22 @@
 
 //! Parses commandline arguments into an instance of `EclipseParams`
 
+use super::errors::EclipseLibErr;
 use super::params::EclipseParams;
-use eclipse_common::arg_parser::{ListParseStyle, Parser};
+use eclipse_common::arg_parser::Parser;
 use eclipse_common::eclipse_params_flags::*;
+use eclipse_common::option_registry::register_options;
 
 /// Parses the given arguments and returns the parsed parameters (as an `EclipseParams`)
 /// and the arguments that were not able to be parsed (as the second element of the tuple).
-pub(super) fn parse_args<'a, T: AsRef<str>>(args: &'a [T]) -> (EclipseParams, Vec<&'a str>) {
+/// Returns an error if the value of a typed option is invalid.
+pub(super) fn parse_args<'a, T: AsRef<str>>(
+    args: &'a [T],
+) -> Result<(EclipseParams, Vec<&'a str>), EclipseLibErr> {
     let mut parser = Parser::new();
-
-    // Add rules for all parameters
-    let console_id = parser.add_optional_option(CONSOLE);
-    let console_log_id = parser.add_flag(CONSOLELOG);
-    let debug_id = parser.add_optional_option(DEBUG);
-    let os_id = parser.add_option(OS);
-    let arch_id = parser.add_option(OSARCH);
-    let nosplash_id = parser.add_flag(NOSPLASH);
-    let showsplash_id = parser.add_optional_option(SHOWSPLASH);
-    let startup_id = parser.add_option(STARTUP);
-    let vm_id = parser.add_option(VM);
-    let ws_id = parser.add_option(WS);
-    let name_id = parser.add_option(NAME);
-    let protect_id = parser.add_option(PROTECT);
-    let openfile_id = parser.add_list(OPENFILE, ListParseStyle::UntilDashPrefix);
-    let default_action_id = parser.add_option(DEFAULTACTION);
-    let timeout_id = parser.add_option(TIMEOUT);
-    let suppress_errors_id = parser.add_flag(SUPRESSERRORS);
-    let library_id = parser.add_option(LIBRARY);
-    let ini_id = parser.add_option(INI);
-    let append_vmargs_id = parser.add_flag(APPEND_VMARGS);
-    let override_vmargs_id = parser.add_flag(OVERRIDE_VMARGS);
-    let second_thread_id = parser.add_flag(SECOND_THREAD);
-    let perm_gen_id = parser.add_flag(PERM_GEN);
-    let gtk_version_id = parser.add_option(GTK_VERSION);
+    // Options only handled by the launcher executable are registered as well,
+    // since they must not be passed on to the application
+    let ids = register_options(&mut parser);
 
     let iter = args.iter().map(|s| s.as_ref());
     let mut parse_result = parser.parse(iter);
@@ -54,29 +37,43 @@ pub(super) fn parse_args<'a, T: AsRef<str>>(args: &'a [T]) -> (EclipseParams, Ve
 
     //TODO adjust paths of openfile
     let result_params = EclipseParams {
-        console: parse_result.take_optional_option(console_id),
-        console_log: parse_result.take_flag(console_log_id),
-        debug: parse_result.take_optional_option(debug_id),
-        os: parse_result.take_option(os_id),
-        arch: parse_result.take_option(arch_id),
-        nosplash: parse_result.take_flag(nosplash_id),
-        showsplash: parse_result.take_optional_option(showsplash_id),
-        startup: parse_result.take_option(startup_id),
-        vm: parse_result.take_option(vm_id),
-        ws: parse_result.take_option(ws_id),
-        name: parse_result.take_option(name_id),
-        protect: parse_result.take_option(protect_id),
-        openfile: parse_result.take_list(openfile_id),
-        default_action: parse_result.take_option(default_action_id),
-        timeout: parse_result.take_option(timeout_id),
-        suppress_errors: parse_result.take_flag(suppress_errors_id),
-        library: parse_result.take_option(library_id),
-        ini: parse_result.take_option(ini_id),
-        append_vmargs: parse_result.take_flag(append_vmargs_id),
-        override_vmargs: parse_result.take_flag(override_vmargs_id),
-        second_thread: parse_result.take_flag(second_thread_id),
-        perm_gen: parse_result.take_flag(perm_gen_id),
-        gtk_version: parse_result.take_option(gtk_version_id),
+        console: parse_result.take_optional_option(ids.optional_option(CONSOLE)),
+        console_log: parse_result.take_flag(ids.flag(CONSOLELOG)),
+        debug: parse_result.take_optional_option(ids.optional_option(DEBUG)),
+        os: parse_result.take_option(ids.option(OS)),
+        arch: parse_result.take_option(ids.option(OSARCH)),
+        nosplash: parse_result.take_flag(ids.flag(NOSPLASH)),
+        showsplash: parse_result.take_optional_option(ids.optional_option(SHOWSPLASH)),
+        startup: parse_result.take_option(ids.option(STARTUP)),
+        vm: parse_result.take_option(ids.option(VM)),
+        ws: parse_result.take_option(ids.option(WS)),
+        name: parse_result.take_option(ids.option(NAME)),
+        protect: parse_result.take_option(ids.option(PROTECT)),
+        openfile: parse_result.take_list(ids.list(OPENFILE)),
+        default_action: parse_result.take_option(ids.option(DEFAULTACTION)),
+        timeout: parse_result.take_typed_option(ids.typed_option(TIMEOUT))?,
+        suppress_errors: parse_result.take_flag(ids.flag(SUPRESSERRORS)),
+        library: parse_result.take_option(ids.option(LIBRARY)),
+        ini: parse_result.take_option(ids.option(INI)),
+        append_vmargs: parse_result.take_flag(ids.flag(APPEND_VMARGS)),
+        override_vmargs: parse_result.take_flag(ids.flag(OVERRIDE_VMARGS)),
+        second_thread: parse_result.take_flag(ids.flag(SECOND_THREAD)),
+        perm_gen: parse_result.take_option(ids.option(PERM_GEN)),
+        gtk_version: parse_result.take_typed_option(ids.typed_option(GTK_VERSION))?,
+        profile: parse_result.take_option(ids.option(PROFILE)),
+        restart_limit: parse_result.take_typed_option(ids.typed_option(RESTART_LIMIT))?,
+        restart_window: parse_result.take_typed_option(ids.typed_option(RESTART_WINDOW))?,
+        oom_retry: parse_result.take_flag(ids.flag(OOM_RETRY)),
+        startup_timeout: parse_result.take_typed_option(ids.typed_option(STARTUP_TIMEOUT))?,
+        rlimits: parse_result.take_typed_repeated_option(ids.typed_repeated_option(RLIMIT))?,
+        nice: parse_result.take_typed_option(ids.typed_option(NICE))?,
+        io_priority: parse_result.take_typed_option(ids.typed_option(IO_PRIORITY))?,
+        cpu_affinity: parse_result.take_typed_option(ids.typed_option(CPU_AFFINITY))?,
+        heap_percent: parse_result.take_typed_option(ids.typed_option(HEAP_PERCENT))?,
+        env: parse_result.take_typed_repeated_option(ids.typed_repeated_option(ENV))?,
+        unset_env: parse_result.take_repeated_option(ids.repeated_option(UNSET_ENV)),
+        clean_env: parse_result.take_flag(ids.flag(CLEAN_ENV)),
+        keep_env: parse_result.take_repeated_option(ids.repeated_option(KEEP_ENV)),
     };
-    (result_params, remainder)
+    Ok((result_params, remainder))
 }
@@ -15,9 +15,14 @@
 
 //! Provides this crate's error type `EclipseLibErr`.
 
+use crate::vm_launch::{CrashLog, RestartReason};
+use eclipse_common::arg_parser::OptionValueError;
+use eclipse_common::ini_reader::IniError;
+use eclipse_common::ini_variables::VariableError;
 use std::error::Error;
 use std::fmt;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// This is the error type for this crate. All introduced errors shall be
 /// a variant of this type.
@@ -33,6 +38,32 @@ pub enum EclipseLibErr {
     SharedMemoryReadInvalidStr,
     SharedMemoryIdParseFail,
     LaunchFail(VmLaunchErr),
+    IniReadFail(IniError),
+    ArgFileReadFail(PathBuf, std::io::Error),
+    InvalidOptionValue(OptionValueError),
+    RestartLimitExceeded(RestartLimitErr),
+
+    /// A variable in the value of the environment variable (the `String`)
+    /// set via `--launcher.env` cannot be expanded.
+    EnvVariableFail(String, VariableError),
+}
+
+impl From<RestartLimitErr> for EclipseLibErr {
+    fn from(err: RestartLimitErr) -> EclipseLibErr {
+        EclipseLibErr::RestartLimitExceeded(err)
+    }
+}
+
+impl From<OptionValueError> for EclipseLibErr {
+    fn from(err: OptionValueError) -> EclipseLibErr {
+        EclipseLibErr::InvalidOptionValue(err)
+    }
+}
+
+impl From<IniError> for EclipseLibErr {
+    fn from(err: IniError) -> EclipseLibErr {
+        EclipseLibErr::IniReadFail(err)
+    }
 }
 
 impl From<VmLaunchErr> for EclipseLibErr {
@@ -45,14 +76,55 @@ impl From<VmLaunchErr> for EclipseLibErr {
 
 impl fmt::Display for EclipseLibErr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // TODO: implement
-        write!(f, "EclipseLibErr")
+        // TODO: implement for remaining variants
+        match self {
+            EclipseLibErr::IniReadFail(err) => write!(f, "{}", err),
+            EclipseLibErr::ArgFileReadFail(path, err) => {
+                write!(f, "Argument file {} cannot be read ({})", path.display(), err)
+            }
+            EclipseLibErr::InvalidOptionValue(err) => write!(f, "{}", err),
+            EclipseLibErr::RestartLimitExceeded(err) => write!(f, "{}", err),
+            EclipseLibErr::EnvVariableFail(name, err) => {
+                write!(f, "{} in the value of environment variable {}", err, name)
+            }
+            EclipseLibErr::LaunchFail(VmLaunchErr::RunFail(err)) => write!(f, "{}", err),
+            _ => write!(f, "EclipseLibErr"),
+        }
     }
 }
 
 impl Error for EclipseLibErr {
 }
 
+/// The JVM requested more restarts within the restart window than allowed.
+/// Holds the reasons of the recent restarts, the last one being the rejected restart.
+#[derive(Debug)]
+pub struct RestartLimitErr {
+    pub max_restarts: u32,
+    pub window: Duration,
+    pub reasons: Vec<RestartReason>,
+}
+
+impl fmt::Display for RestartLimitErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The Java VM requested more than {} restarts within {}s, giving up. Recent restarts:",
+            self.max_restarts,
+            self.window.as_secs()
+        )?;
+        for reason in &self.reasons {
+            write!(f, "\n  exit code {}", reason.exit_code)?;
+            let exit_data = reason.exit_data.trim();
+            if !exit_data.is_empty() {
+                // exit data may be an argument list separated by '\n'
+                write!(f, ", exit data: {}", exit_data.replace('\n', " "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub enum VmLaunchErr {
     StartFail(VmStartErr),
@@ -78,6 +150,59 @@ pub enum VmRunErr {
     TerminationErr(std::io::Error),
     UnknownErr,
     UnexpectedReturnValue,
+
+    /// The JVM crashed and wrote a fatal error log. The `exit_code` is `None`
+    /// if the JVM was terminated by a signal.
+    Crashed {
+        exit_code: Option<i32>,
+        crash_log: CrashLog,
+    },
+
+    /// The JVM did not complete its startup within the `timeout` and was terminated
+    /// on request of the user. `thread_dump` is the file a thread dump was written to.
+    StartupTimeout {
+        timeout: Duration,
+        thread_dump: Option<PathBuf>,
+    },
+}
+
+impl fmt::Display for VmRunErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VmRunErr::FailureReturnCode(code) => {
+                write!(f, "The Java VM terminated with exit code {}", code)
+            }
+            VmRunErr::TerminationErr(err) => {
+                write!(f, "Checking the termination of the Java VM failed: {}", err)
+            }
+            VmRunErr::UnknownErr => write!(f, "The Java VM terminated unexpectedly"),
+            VmRunErr::UnexpectedReturnValue => {
+                write!(f, "The Java VM returned an unexpected value")
+            }
+            VmRunErr::Crashed {
+                exit_code: Some(code),
+                crash_log,
+            } => write!(f, "The Java VM crashed (exit code {}).\n{}", code, crash_log),
+            VmRunErr::Crashed {
+                exit_code: None,
+                crash_log,
+            } => write!(f, "The Java VM crashed.\n{}", crash_log),
+            VmRunErr::StartupTimeout {
+                timeout,
+                thread_dump,
+            } => {
+                write!(
+                    f,
+                    "The Java VM did not start within {} seconds and was terminated.",
+                    timeout.as_secs()
+                )?;
+                match thread_dump {
+                    Some(path) => write!(f, "\nThread dump: {}", path.display()),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
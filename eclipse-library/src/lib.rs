@@ -142,8 +142,12 @@ mod ee_params_parse;
 mod errors;
 mod iter_ptr;
 mod jar_lookup;
+mod memory_limit;
 mod native_str_read;
+mod oom_retry;
 mod params;
+mod private_dir;
+mod restart_budget;
 mod run;
 mod shared_mem;
 mod splash;
@@ -250,7 +254,7 @@ fn run_internal(args: Vec<String>, vm_args: Vec<String>) -> i32 {
 
     // TODO: turn result into return code and show error messages
     if let Err(e) = result {
-        eprintln!("{:#?}", e);
+        eprintln!("{}\nDetails: \n{:#?}", e, e);
     }
 
     // Free global memory
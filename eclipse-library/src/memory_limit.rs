@@ -0,0 +1,152 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides `available_memory`, the amount of memory the JVM may use.
+//! On Linux this is the memory limit of the cgroup (v2 `memory.max` or v1
+//! `memory.limit_in_bytes`) the launcher runs in, e.g. inside a container, or
+//! the total memory from `/proc/meminfo` if the cgroup is not constrained.
+//! On other platforms it is the physical memory of the machine.
+
+/// Returns the memory available to this process and its children in bytes,
+/// or `None` if it cannot be determined.
+#[cfg(target_os = "linux")]
+pub fn available_memory() -> Option<u64> {
+    use std::fs::read_to_string;
+    let total = read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|meminfo| parse_meminfo_total(&meminfo));
+    let cgroup_limit = read_to_string("/proc/self/cgroup")
+        .ok()
+        .and_then(|cgroups| {
+            cgroup_limit_files(&cgroups)
+                .into_iter()
+                .find_map(|file| read_to_string(file).ok())
+        })
+        .and_then(|limit| parse_cgroup_limit(&limit));
+    // cgroup v1 reports "no limit" as a huge number, which is capped by the total memory
+    match (cgroup_limit, total) {
+        (Some(limit), Some(total)) => Some(limit.min(total)),
+        (limit, total) => limit.or(total),
+    }
+}
+
+/// Returns the size of the physical memory in bytes
+#[cfg(all(not(target_os = "linux"), not(target_os = "windows")))]
+pub fn available_memory() -> Option<u64> {
+    // safe, sysconf has no preconditions
+    let (pages, page_size) = unsafe {
+        (
+            libc::sysconf(libc::_SC_PHYS_PAGES),
+            libc::sysconf(libc::_SC_PAGESIZE),
+        )
+    };
+    if pages <= 0 || page_size <= 0 {
+        return None;
+    }
+    (pages as u64).checked_mul(page_size as u64)
+}
+
+/// Returns the size of the physical memory in bytes
+#[cfg(target_os = "windows")]
+pub fn available_memory() -> Option<u64> {
+    use winapi::um::sysinfoapi::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+    let mut status: MEMORYSTATUSEX = unsafe { std::mem::zeroed() };
+    status.dwLength = std::mem::size_of::<MEMORYSTATUSEX>() as u32;
+    // safe, status is initialized with its size as required
+    if unsafe { GlobalMemoryStatusEx(&mut status) } == 0 {
+        return None;
+    }
+    Some(status.ullTotalPhys)
+}
+
+/// Returns the files holding the memory limit of the cgroups listed in the content of
+/// `/proc/self/cgroup`, in the order they should be tried. The cgroup v2 entry has the
+/// form `0::<path>`, a cgroup v1 entry `<id>:<controllers>:<path>`. Inside a container
+/// the cgroup is usually mounted as root of the hierarchy, which is tried as fallback.
+#[cfg(target_os = "linux")]
+fn cgroup_limit_files(cgroups: &str) -> Vec<String> {
+    const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+    let mut files = Vec::new();
+    for line in cgroups.lines() {
+        let mut parts = line.splitn(3, ':');
+        let (controllers, path) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(_), Some(controllers), Some(path)) => (controllers, path.trim_end_matches('/')),
+            _ => continue,
+        };
+        if controllers.is_empty() {
+            files.push(format!("{}{}/memory.max", CGROUP_ROOT, path));
+            files.push(format!("{}/memory.max", CGROUP_ROOT));
+        } else if controllers.split(',').any(|c| c == "memory") {
+            let dir = format!("{}/memory", CGROUP_ROOT);
+            files.push(format!("{}{}/memory.limit_in_bytes", dir, path));
+            files.push(format!("{}/memory.limit_in_bytes", dir));
+        }
+    }
+    files
+}
+
+/// Parses the content of a cgroup memory limit file, `max` means no limit
+#[cfg(target_os = "linux")]
+fn parse_cgroup_limit(limit: &str) -> Option<u64> {
+    limit.trim().parse().ok()
+}
+
+/// Returns the `MemTotal` entry of the content of `/proc/meminfo` in bytes
+#[cfg(target_os = "linux")]
+fn parse_meminfo_total(meminfo: &str) -> Option<u64> {
+    let total = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))?;
+    let kib = total
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    kib.checked_mul(1024)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod test {
+    use super::{cgroup_limit_files, parse_cgroup_limit, parse_meminfo_total};
+
+    #[test]
+    fn test_cgroup_limit_files() {
+        let v2 = cgroup_limit_files("0::/user.slice/user-1000.slice\n");
+        assert_eq!(
+            v2,
+            [
+                "/sys/fs/cgroup/user.slice/user-1000.slice/memory.max",
+                "/sys/fs/cgroup/memory.max"
+            ]
+        );
+        let v1 = cgroup_limit_files("4:cpu,cpuacct:/docker/42\n3:memory:/docker/42\n");
+        assert_eq!(
+            v1,
+            [
+                "/sys/fs/cgroup/memory/docker/42/memory.limit_in_bytes",
+                "/sys/fs/cgroup/memory/memory.limit_in_bytes"
+            ]
+        );
+        assert!(cgroup_limit_files("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        assert_eq!(parse_cgroup_limit("2147483648\n"), Some(2_147_483_648));
+        assert_eq!(parse_cgroup_limit("max\n"), None);
+        let meminfo = "MemTotal:       16314500 kB\nMemFree:         8123456 kB\n";
+        assert_eq!(parse_meminfo_total(meminfo), Some(16_314_500 * 1024));
+    }
+}
@@ -0,0 +1,176 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides the heap size computations for `--launcher.oomRetry`.
+//! If the JVM exits due to an `OutOfMemoryError`, it is restarted with a
+//! maximum heap size (`-Xmx`) scaled up by `HEAP_SCALE_FACTOR`, capped by the
+//! memory available to the JVM (see `memory_limit`).
+
+use crate::memory_limit::available_memory;
+use crate::private_dir::PrivateDir;
+use std::path::PathBuf;
+
+pub const MAX_HEAP_ARG: &str = "-Xmx";
+
+/// Factor the maximum heap size is multiplied with on every retry
+const HEAP_SCALE_FACTOR: u64 = 2;
+
+/// Upper bound of retries, in case the available memory cannot be determined
+pub const MAX_OOM_RETRIES: u32 = 4;
+
+/// Without `-Xmx` the JVM uses a quarter of the available memory as maximum heap size
+const DEFAULT_HEAP_DIVISOR: u64 = 4;
+
+const KIB: u64 = 1024;
+pub(crate) const MIB: u64 = 1024 * KIB;
+const GIB: u64 = 1024 * MIB;
+
+/// How the launcher detects that the JVM terminated because of an `OutOfMemoryError`
+#[derive(Debug)]
+pub struct OomExit {
+    exit_code: i32,
+
+    /// The file the JVM writes on an `OutOfMemoryError` if the exit code is not
+    /// distinctive, together with the directory holding it
+    dump_file: Option<(PrivateDir, PathBuf)>,
+}
+
+impl OomExit {
+    /// The JVM terminates with `exit_code`, which it does not use otherwise
+    pub fn exit_code(exit_code: i32) -> OomExit {
+        OomExit {
+            exit_code,
+            dump_file: None,
+        }
+    }
+
+    /// The JVM writes `dump_file` in `dir` and terminates with `exit_code`
+    pub fn dump_file(exit_code: i32, dir: PrivateDir, dump_file: PathBuf) -> OomExit {
+        OomExit {
+            exit_code,
+            dump_file: Some((dir, dump_file)),
+        }
+    }
+
+    /// Returns `true` if the JVM terminated with `exit_code` because of an
+    /// `OutOfMemoryError`. The dump file is removed, so it does not count for
+    /// the next launch.
+    pub fn is_oom_exit(&self, exit_code: i32) -> bool {
+        if exit_code != self.exit_code {
+            return false;
+        }
+        match &self.dump_file {
+            Some((_, dump_file)) => std::fs::remove_file(dump_file).is_ok(),
+            None => true,
+        }
+    }
+}
+
+/// Returns the maximum heap size for the next launch of a JVM that ran out of
+/// memory with the given `-Xmx` argument (`None` if not specified), as `-Xmx`
+/// argument. Returns `None` if the heap size cannot be increased any further.
+pub fn next_max_heap_arg(max_heap_arg: Option<&str>) -> Option<String> {
+    let available = available_memory();
+    let current = match max_heap_arg {
+        Some(arg) => parse_heap_size(arg.strip_prefix(MAX_HEAP_ARG)?)?,
+        None => available? / DEFAULT_HEAP_DIVISOR,
+    };
+    let next = scale_heap_size(current, available)?;
+    Some(format!("{}{}", MAX_HEAP_ARG, format_heap_size(next)))
+}
+
+/// Scales the `current` heap size up, capped by the `available` memory.
+/// Returns `None` if the cap is already reached.
+fn scale_heap_size(current: u64, available: Option<u64>) -> Option<u64> {
+    let scaled = current.saturating_mul(HEAP_SCALE_FACTOR);
+    let next = match available {
+        Some(available) => scaled.min(available),
+        None => scaled,
+    };
+    // round down to whole megabytes, so the value is readable in the ini file
+    let next = next / MIB * MIB;
+    if next > current {
+        Some(next)
+    } else {
+        None
+    }
+}
+
+/// Parses a memory size as given to `-Xmx`, e.g. `512m`, `4G` or `1073741824`.
+fn parse_heap_size(size: &str) -> Option<u64> {
+    let (digits, unit) = match size.char_indices().last()? {
+        (index, c) if c.is_ascii_alphabetic() => (&size[..index], c.to_ascii_lowercase()),
+        _ => (size, 'b'),
+    };
+    let factor = match unit {
+        'b' => 1,
+        'k' => KIB,
+        'm' => MIB,
+        'g' => GIB,
+        't' => GIB * KIB,
+        _ => return None,
+    };
+    digits.parse::<u64>().ok()?.checked_mul(factor)
+}
+
+/// Formats a heap size in gigabytes, if possible, otherwise in megabytes
+pub(crate) fn format_heap_size(bytes: u64) -> String {
+    match bytes % GIB {
+        0 => format!("{}g", bytes / GIB),
+        _ => format!("{}m", bytes / MIB),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{format_heap_size, parse_heap_size, scale_heap_size, OomExit, GIB, MIB};
+    use crate::private_dir::PrivateDir;
+
+    #[test]
+    fn test_parse_heap_size() {
+        assert_eq!(parse_heap_size("512m"), Some(512 * MIB));
+        assert_eq!(parse_heap_size("4G"), Some(4 * GIB));
+        assert_eq!(parse_heap_size("2048k"), Some(2 * MIB));
+        assert_eq!(parse_heap_size("1073741824"), Some(GIB));
+        assert_eq!(parse_heap_size("4x"), None);
+        assert_eq!(parse_heap_size("g"), None);
+    }
+
+    #[test]
+    fn test_format_heap_size() {
+        assert_eq!(format_heap_size(8 * GIB), "8g");
+        assert_eq!(format_heap_size(1536 * MIB), "1536m");
+    }
+
+    #[test]
+    fn test_scale_heap_size_capped() {
+        assert_eq!(scale_heap_size(GIB, Some(16 * GIB)), Some(2 * GIB));
+        assert_eq!(scale_heap_size(6 * GIB, Some(8 * GIB)), Some(8 * GIB));
+        assert_eq!(scale_heap_size(8 * GIB, Some(8 * GIB)), None);
+        assert_eq!(scale_heap_size(GIB, None), Some(2 * GIB));
+    }
+
+    #[test]
+    fn test_oom_exit_dump_file() {
+        let dir = PrivateDir::create("eclipse_oom_test_").unwrap();
+        let dump_file = dir.path().join("oom.txt");
+        let oom_exit = OomExit::dump_file(1, dir, dump_file.clone());
+        assert!(!oom_exit.is_oom_exit(1));
+        std::fs::write(&dump_file, "javacore").unwrap();
+        assert!(!oom_exit.is_oom_exit(2));
+        assert!(oom_exit.is_oom_exit(1));
+        assert!(!oom_exit.is_oom_exit(1));
+        assert!(OomExit::exit_code(3).is_oom_exit(3));
+    }
+}
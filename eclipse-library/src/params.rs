@@ -12,7 +12,9 @@
  *     Max Bureck (Fraunhofer FOKUS)
  *******************************************************************************/
 
-use eclipse_common::arg_parser::OptionalParam;
+use crate::vm_launch::{CpuAffinity, EnvVar, IoPriority, Niceness, ResourceLimit};
+use eclipse_common::arg_parser::{parse_choice, OptionValue, OptionalParam};
+use std::time::Duration;
 
 #[derive(Default, Debug)]
 pub struct EclipseParams {
@@ -64,7 +66,9 @@ pub struct EclipseParams {
     pub protect: Option<String>,
     pub openfile: Option<Vec<String>>,
     pub default_action: Option<String>,
-    pub timeout: Option<String>,
+
+    /// Time to wait for a running instance to process an open file request
+    pub timeout: Option<Duration>,
     pub suppress_errors: bool,
 
     /// The location of the eclipse executable's companion shared library.  
@@ -85,8 +89,83 @@ pub struct EclipseParams {
     pub append_vmargs: bool,
     pub override_vmargs: bool,
     pub second_thread: bool,
-    pub perm_gen: bool,
-    pub gtk_version: Option<String>,
+
+    /// The value for `-XX:MaxPermSize` (e.g. `256m`). The VM argument is only added
+    /// for HotSpot VMs older than Java 8, since newer VMs do not have a permanent generation.
+    pub perm_gen: Option<String>,
+
+    /// The major version of GTK to use on Linux
+    pub gtk_version: Option<GtkVersion>,
+
+    /// The profile sections of the ini files to use. If not set,
+    /// the profile is read from the environment variable `ECLIPSE_PROFILE`.
+    pub profile: Option<String>,
+
+    /// The maximum number of JVM restarts allowed within `restart_window`
+    pub restart_limit: Option<u32>,
+
+    /// The time window in which JVM restarts are counted against `restart_limit`
+    pub restart_window: Option<Duration>,
+
+    /// If set, the JVM is restarted with a larger maximum heap size
+    /// after it terminated due to an `OutOfMemoryError`
+    pub oom_retry: bool,
+
+    /// Time in which the JVM has to complete its startup, before a thread dump is
+    /// requested and the user is asked whether to keep waiting
+    pub startup_timeout: Option<Duration>,
+
+    /// Resource limits of the JVM, given by `--launcher.rlimit`
+    pub rlimits: Vec<ResourceLimit>,
+
+    /// Process scheduling settings of the JVM, given by `--launcher.nice`,
+    /// `--launcher.ioPriority` and `--launcher.cpuAffinity`
+    pub nice: Option<Niceness>,
+    pub io_priority: Option<IoPriority>,
+    pub cpu_affinity: Option<CpuAffinity>,
+
+    /// Percentage of the available memory used as maximum heap size,
+    /// if the user did not specify `-Xmx`
+    pub heap_percent: Option<HeapPercent>,
+
+    /// Environment variables of the JVM, given by `--launcher.env` on the command line.
+    /// The values of ini files are read by `env_vars_from_ini`.
+    pub env: Vec<EnvVar>,
+
+    /// Environment variables removed for the JVM, given by `--launcher.unsetEnv`
+    pub unset_env: Vec<String>,
+
+    /// If set, the JVM only inherits essential and the `keep_env` environment variables
+    pub clean_env: bool,
+
+    /// Environment variables kept by `--launcher.cleanEnv`, given by `--launcher.keepEnv`
+    pub keep_env: Vec<String>,
+}
+
+/// Supported values of `--launcher.GTK_version`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum GtkVersion {
+    Gtk2,
+    Gtk3,
+}
+
+impl OptionValue for GtkVersion {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        parse_choice(value, &[("2", GtkVersion::Gtk2), ("3", GtkVersion::Gtk3)])
+    }
+}
+
+/// Value of `--launcher.heapPercent`, from 1 to 100
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct HeapPercent(pub u8);
+
+impl OptionValue for HeapPercent {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value.parse() {
+            Ok(percent) if (1..=100).contains(&percent) => Ok(HeapPercent(percent)),
+            _ => Err("expected a percentage from 1 to 100".to_string()),
+        }
+    }
 }
 
 #[derive(Default, Debug)]
@@ -0,0 +1,105 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides the type `PrivateDir`, a directory in the temp folder for
+//! files the JVM writes to signal the launcher. Files with predictable names directly
+//! in the shared temp folder could be created beforehand by other users, e.g. as
+//! symbolic links to files of the user running the launcher. A `PrivateDir` is
+//! newly created with a unique name and is only accessible by the current user.
+
+use std::fs::DirBuilder;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of names tried, in case directories with the same name already exist
+const MAX_ATTEMPTS: u32 = 100;
+
+/// A directory in the temp folder, removed with all its content when dropped.
+/// See module documentation.
+#[derive(Debug)]
+pub struct PrivateDir {
+    path: PathBuf,
+}
+
+impl PrivateDir {
+    /// Creates a new directory in the temp folder, whose name starts with `prefix`.
+    /// Never reuses an existing directory.
+    pub fn create(prefix: &str) -> Result<PrivateDir, Error> {
+        let temp_dir = std::env::temp_dir();
+        let pid = std::process::id();
+        let mut builder = DirBuilder::new();
+        set_private_mode(&mut builder);
+        for attempt in 0..MAX_ATTEMPTS {
+            // not meant to be unguessable, creation fails if the directory exists
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.subsec_nanos())
+                .unwrap_or_default();
+            let name = format!("{}{}_{:x}{}", prefix, pid, nanos, attempt);
+            let path = temp_dir.join(name);
+            match builder.create(&path) {
+                Ok(()) => return Ok(PrivateDir { path }),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Err(Error::new(
+            ErrorKind::AlreadyExists,
+            "no unused directory name found",
+        ))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PrivateDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn set_private_mode(builder: &mut DirBuilder) {
+    use std::os::unix::fs::DirBuilderExt;
+    builder.mode(0o700);
+}
+
+/// The temp folder on Windows is located in the user profile, which is private already
+#[cfg(target_os = "windows")]
+fn set_private_mode(_builder: &mut DirBuilder) {}
+
+#[cfg(test)]
+mod test {
+    use super::PrivateDir;
+
+    #[test]
+    fn test_private_dir_created_and_removed() {
+        let dir = PrivateDir::create("eclipse_private_dir_test_").unwrap();
+        let path = dir.path().to_path_buf();
+        let other = PrivateDir::create("eclipse_private_dir_test_").unwrap();
+        assert_ne!(path, other.path());
+        std::fs::write(path.join("file"), "content").unwrap();
+        #[cfg(not(target_os = "windows"))]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        drop(dir);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,156 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides the type `RestartBudget`, which protects against
+//! JVMs requesting restarts over and over again (e.g. because of a broken
+//! plugin). Only a limited number of restarts is allowed within a time window,
+//! and subsequent restarts within the window are delayed exponentially.
+
+use crate::errors::RestartLimitErr;
+use crate::vm_launch::RestartReason;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Default for the maximum number of restarts within the restart window
+pub const DEFAULT_MAX_RESTARTS: u32 = 5;
+
+/// Default for the time window in which restarts are counted
+pub const DEFAULT_RESTART_WINDOW: Duration = Duration::from_secs(60);
+
+/// Delay before the second restart within the window, doubled for every further restart
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+struct Restart {
+    time: Instant,
+    reason: RestartReason,
+}
+
+/// Keeps track of the JVM restarts and decides if, and after which delay,
+/// a requested restart may be performed.
+pub struct RestartBudget {
+    max_restarts: u32,
+    window: Duration,
+    recent: VecDeque<Restart>,
+}
+
+impl RestartBudget {
+    pub fn new(max_restarts: u32, window: Duration) -> RestartBudget {
+        RestartBudget {
+            max_restarts,
+            window,
+            recent: VecDeque::new(),
+        }
+    }
+
+    /// Records a restart requested at `now` for the given `reason`. Returns the delay
+    /// to wait before restarting the JVM. The first restart within the window is not
+    /// delayed. If the maximum number of restarts within the window is already reached,
+    /// an error listing the recent restarts (including the rejected one) is returned.
+    pub fn request_restart(
+        &mut self,
+        reason: RestartReason,
+        now: Instant,
+    ) -> Result<Duration, RestartLimitErr> {
+        let window = self.window;
+        while let Some(oldest) = self.recent.front() {
+            if now.duration_since(oldest.time) <= window {
+                break;
+            }
+            self.recent.pop_front();
+        }
+
+        let restart_count = self.recent.len();
+        if restart_count >= self.max_restarts as usize {
+            let mut reasons: Vec<RestartReason> = self
+                .recent
+                .drain(..)
+                .map(|restart| restart.reason)
+                .collect();
+            reasons.push(reason);
+            return Err(RestartLimitErr {
+                max_restarts: self.max_restarts,
+                window,
+                reasons,
+            });
+        }
+
+        self.recent.push_back(Restart { time: now, reason });
+        Ok(backoff(restart_count))
+    }
+}
+
+/// Returns the delay before a restart, if `restart_count` restarts already
+/// happened within the restart window.
+fn backoff(restart_count: usize) -> Duration {
+    if restart_count == 0 {
+        return Duration::from_secs(0);
+    }
+    // saturate the shift, larger values exceed MAX_BACKOFF anyway
+    let factor = 1u32 << (restart_count - 1).min(16);
+    (INITIAL_BACKOFF * factor).min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RestartBudget, MAX_BACKOFF};
+    use crate::vm_launch::RestartReason;
+    use std::time::{Duration, Instant};
+
+    fn reason(exit_code: i32) -> RestartReason {
+        RestartReason {
+            exit_code,
+            exit_data: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_exponentially() {
+        let mut budget = RestartBudget::new(20, Duration::from_secs(600));
+        let now = Instant::now();
+        let delays: Vec<u128> = (0..4)
+            .map(|_| budget.request_restart(reason(23), now).unwrap().as_millis())
+            .collect();
+        assert_eq!(delays, [0, 500, 1000, 2000]);
+        for _ in 0..12 {
+            budget.request_restart(reason(23), now).unwrap();
+        }
+        assert_eq!(
+            budget.request_restart(reason(23), now).unwrap(),
+            MAX_BACKOFF
+        );
+    }
+
+    #[test]
+    fn test_limit_exceeded() {
+        let mut budget = RestartBudget::new(2, Duration::from_secs(60));
+        let now = Instant::now();
+        budget.request_restart(reason(23), now).unwrap();
+        budget.request_restart(reason(24), now).unwrap();
+        let err = budget.request_restart(reason(23), now).unwrap_err();
+        assert_eq!(err.max_restarts, 2);
+        let codes: Vec<i32> = err.reasons.iter().map(|r| r.exit_code).collect();
+        assert_eq!(codes, [23, 24, 23]);
+    }
+
+    #[test]
+    fn test_restarts_outside_window_are_forgotten() {
+        let mut budget = RestartBudget::new(1, Duration::from_secs(60));
+        let start = Instant::now();
+        budget.request_restart(reason(23), start).unwrap();
+        let later = start + Duration::from_secs(61);
+        let delay = budget.request_restart(reason(23), later).unwrap();
+        assert_eq!(delay, Duration::from_secs(0));
+    }
+}
@@ -17,18 +17,27 @@
 
 use core::fmt::Debug;
 use crate::eclipse_params_parse::parse_args;
-use crate::errors::EclipseLibErr;
+use crate::errors::{EclipseLibErr, VmLaunchErr, VmRunErr};
 use crate::jar_lookup::find_startup_jar;
+use crate::oom_retry::{next_max_heap_arg, OomExit, MAX_HEAP_ARG, MAX_OOM_RETRIES};
 use crate::params::EclipseParams;
+use crate::restart_budget::{RestartBudget, DEFAULT_MAX_RESTARTS, DEFAULT_RESTART_WINDOW};
 use crate::shared_mem::{create_shared_mem, SharedMem, MAX_SHARED_LENGTH};
-use crate::vm_args_read::complete_vm_args;
+use crate::vm_args_read::{complete_vm_args, read_launcher_ini_chain};
 use crate::vm_command::{get_vm_command, VmArgs};
-use crate::vm_launch::{JavaLauncher, StopAction};
+use crate::vm_launch::{
+    env_vars_from_ini, Environment, JavaLauncher, ResourceLimits, Scheduling, StartupWatchdog,
+    StopAction,
+};
 use crate::vm_lookup::{determine_vm, JvmLaunchMode};
 use crate::console_detection::is_console_launcher;
+use eclipse_common::eclipse_params_flags::CONFIG;
+use eclipse_common::messagebox::display_message;
 use eclipse_common::name_util::get_default_official_name_from_str;
 use eclipse_common::path_util::strip_unc_prefix;
 use std::path::Path;
+use std::thread;
+use std::time::Instant;
 
 const ACTION_OPENFILE: &str = "openFile";
 
@@ -38,7 +47,7 @@ pub fn run_framework<S: AsRef<str> + Debug>(
     initial_args: &[S],
     library: &Path,
 ) -> Result<(), EclipseLibErr> {
-    let (mut parsed_args, remaining_args) = parse_args(args);
+    let (mut parsed_args, remaining_args) = parse_args(args)?;
 
     let program = args.get(0).map(|s| s.as_ref()).unwrap_or_default();
     let program_path = Path::new(program);
@@ -71,11 +80,19 @@ pub fn run_framework<S: AsRef<str> + Debug>(
     let jar_file_str = strip_unc_prefix(&jar_file.to_string_lossy()).to_string();
     parsed_args.startup = Some(jar_file_str);
 
-    let win_console = is_console_launcher();
-    let complete_vm_args = complete_vm_args(&vm_args, &parsed_args, &program_path, win_console)?;
-
     let vm_path = determine_vm(&parsed_args, program_dir)?;
 
+    let win_console = is_console_launcher();
+    let ini_chain = read_launcher_ini_chain(&parsed_args, program_path, win_console)?;
+    let complete_vm_args = complete_vm_args(
+        vm_args,
+        &parsed_args,
+        program_path,
+        win_console,
+        &vm_path,
+        &ini_chain,
+    )?;
+
     // TODO: reuse running eclipse if params.openfile is Some
     // TODO: on windows: if( launchMode == LAUNCH_JNI && (debug || needConsole) ) createConsole
     // TODO: show splash if needed
@@ -90,16 +107,57 @@ pub fn run_framework<S: AsRef<str> + Debug>(
         &parsed_args,
         shared_data.get_id(),
         program_path,
-    );
+    )?;
 
     let mut vm_launcher = JavaLauncher::new(&vm_path, &vm_command, &jar_file, &shared_data);
+    let mut restart_budget = RestartBudget::new(
+        parsed_args.restart_limit.unwrap_or(DEFAULT_MAX_RESTARTS),
+        parsed_args.restart_window.unwrap_or(DEFAULT_RESTART_WINDOW),
+    );
+    let mut oom_retries = 0;
+    if let Some(timeout) = parsed_args.startup_timeout {
+        let name = parsed_args.name.as_deref().unwrap_or_default();
+        match StartupWatchdog::new(timeout, name, !parsed_args.suppress_errors) {
+            Ok(watchdog) => vm_launcher.set_startup_watchdog(watchdog),
+            Err(err) => eprintln!("Warning: startup is not watched: {}", err),
+        }
+    }
+    let (resource_limits, mut warnings) = ResourceLimits::resolve(&parsed_args.rlimits);
+    warnings.extend(vm_launcher.set_resource_limits(resource_limits));
+    for warning in warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    apply_scheduling(&parsed_args);
+    // values of the command line win over the ones of ini files
+    let mut env_vars = env_vars_from_ini(&ini_chain);
+    env_vars.extend(parsed_args.env.iter().cloned());
+    let environment = Environment::resolve(
+        parsed_args.clean_env,
+        &parsed_args.keep_env,
+        &parsed_args.unset_env,
+        &env_vars,
+        program_dir,
+    )?;
+    vm_launcher.set_environment(environment);
 
     // While the Java VM should be restarted
     loop {
         // TODO: store vm command as message
         // TODO: if -debug, print start command to console
         // TODO: Handle result (restart if necessary)
-        match vm_launcher.launch()? {
+        let stop_action = match vm_launcher.launch() {
+            Ok(stop_action) => stop_action,
+            Err(err) => match oom_retry_heap_arg(&err, vm_command.oom_exit.as_ref(), &vm_launcher) {
+                Some(max_heap_arg) if oom_retries < MAX_OOM_RETRIES => {
+                    notify_oom_retry(&max_heap_arg, program, &parsed_args);
+                    vm_launcher.replace_vm_arg(MAX_HEAP_ARG, &max_heap_arg);
+                    oom_retries += 1;
+                    continue;
+                }
+                _ => return Err(show_crash_dialog(err, &parsed_args)),
+            },
+        };
+        match stop_action {
             StopAction::Nothing => {
                 // No reastart needed, stop the loop
                 break;
@@ -112,8 +170,10 @@ pub fn run_framework<S: AsRef<str> + Debug>(
                 // TODO: restart lauchner with current args
                 break;
             },
-            StopAction::RestartVM => {
-                // Nothing to do, remain in restart loop
+            StopAction::RestartVM(reason) => {
+                // Remain in restart loop, unless the VM keeps requesting restarts
+                let delay = restart_budget.request_restart(reason, Instant::now())?;
+                thread::sleep(delay);
             },
         }
     }
@@ -124,6 +184,77 @@ pub fn run_framework<S: AsRef<str> + Debug>(
     Ok(())
 }
 
+/// Applies the process scheduling options to this process, so they take effect for
+/// the JVM in both launch modes. Failures are reported as warnings, successfully
+/// applied settings are printed if `-debug` is set.
+fn apply_scheduling(params: &EclipseParams) {
+    let scheduling = Scheduling {
+        nice: params.nice,
+        io_priority: params.io_priority,
+        cpu_affinity: params.cpu_affinity.clone(),
+    };
+    for result in scheduling.apply() {
+        match result {
+            Ok(applied) if params.debug.is_set() => eprintln!("{}", applied),
+            Ok(_) => {}
+            Err(err) => eprintln!("Warning: {}", err),
+        }
+    }
+}
+
+/// If the JVM terminated with `err` because it ran out of memory (detected via
+/// `oom_exit` if `--launcher.oomRetry` is set), returns the `-Xmx` argument to
+/// relaunch the JVM with. Returns `None` if the heap size cannot be increased further.
+fn oom_retry_heap_arg<S: SharedMem>(
+    err: &EclipseLibErr,
+    oom_exit: Option<&OomExit>,
+    vm_launcher: &JavaLauncher<'_, S>,
+) -> Option<String> {
+    match err {
+        EclipseLibErr::LaunchFail(VmLaunchErr::RunFail(VmRunErr::FailureReturnCode(code)))
+            if oom_exit.is_some_and(|oom_exit| oom_exit.is_oom_exit(*code)) =>
+        {
+            next_max_heap_arg(vm_launcher.vm_arg(MAX_HEAP_ARG))
+        }
+        _ => None,
+    }
+}
+
+/// Tells the user that the JVM is restarted with the larger `max_heap_arg`,
+/// and how to persist the setting in the ini file of the `program`.
+fn notify_oom_retry(max_heap_arg: &str, program: &str, params: &EclipseParams) {
+    let heap_size = max_heap_arg.trim_start_matches(MAX_HEAP_ARG);
+    let name = params.name.as_deref().unwrap_or_default();
+    let msg = format!(
+        "{} ran out of memory and is restarted with a maximum heap size of {}.\n\
+         To keep this setting, run: {} {} set {} {}",
+        name, heap_size, program, CONFIG, MAX_HEAP_ARG, heap_size
+    );
+    if params.suppress_errors {
+        eprintln!("{}", msg);
+    } else if let Err(err) = display_message(&msg, name) {
+        // message dialog failed, print the message instead
+        eprintln!("{}\n{}", msg, err);
+    }
+}
+
+/// Shows the summary of a JVM crash in a message dialog, unless errors are suppressed.
+/// Returns the given `err`, so it is reported on exit as well.
+fn show_crash_dialog(err: EclipseLibErr, params: &EclipseParams) -> EclipseLibErr {
+    let is_crash = matches!(
+        err,
+        EclipseLibErr::LaunchFail(VmLaunchErr::RunFail(VmRunErr::Crashed { .. }))
+    );
+    if is_crash && !params.suppress_errors {
+        let title = params.name.as_deref().unwrap_or_default();
+        // message dialog failed, the error is still printed on exit
+        if let Err(msg) = display_message(&err.to_string(), title) {
+            eprintln!("{}", msg);
+        }
+    }
+    err
+}
+
 /// Based on the default `action` uses `initial_args`
 /// to update the given `params`.
 fn process_default_action<S: AsRef<str>>(
@@ -13,45 +13,103 @@
  *******************************************************************************/
 
 //! This module provides the public function `complete_vm_args` which allows
-//! to combine the JVM arguments specified via command line and ini file(s).
+//! to combine the JVM arguments specified via environment variables, command line
+//! and ini file(s).
 
 use crate::errors::EclipseLibErr;
 use crate::params::EclipseParams;
-use eclipse_common::ini_reader::read_ini;
-#[cfg(target_os = "macos")]
-use eclipse_common::ini_reader::read_ini_lines;
-use std::path::Path;
-#[cfg(target_os = "macos")]
-use std::path::PathBuf;
+use crate::vm_command::split_java_args;
+use crate::vm_lookup::JvmLaunchMode;
+use eclipse_common::compile_params::Platform;
+use eclipse_common::ini_reader::{
+    is_overriding_vm_arg, read_ini_chain, read_ini_file, select_profile, vm_arg_key, IniChain,
+    IniLayer,
+};
+#[cfg(not(target_os = "macos"))]
+use eclipse_common::ini_reader::user_ini_path;
+use std::path::{Path, PathBuf};
 use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Value of `-protect` for installations that must not be modified by users
+static PROTECT_BASE: &str = "base";
+
+/// System property telling the Java application the selected launch profile
+static PROFILE_PROPERTY: &str = "-Declipse.launcher.profile";
+
+/// Environment variable read by the java executable, holding VM arguments
+static JDK_JAVA_OPTIONS: &str = "JDK_JAVA_OPTIONS";
+
+/// Environment variable holding VM arguments for Eclipse
+static ECLIPSE_VMARGS: &str = "ECLIPSE_VMARGS";
 
-static VM_ARGS_PARAM: &str = "-vmargs";
+static ADD_OPENS: &str = "--add-opens";
 
+/// Reads the configuration chain of launcher ini files (see `read_ini_chain`), with the
+/// sections of the selected profile and conditional blocks evaluated for the platform
+/// given by `params`.
+pub fn read_launcher_ini_chain(
+    params: &EclipseParams,
+    program: &Path,
+    win_console: bool,
+) -> Result<IniChain, EclipseLibErr> {
+    let profile = select_profile(params.profile.clone());
+    let platform = launcher_platform(params);
+    let ini_chain =
+        read_ini_chain(&params.ini, program, win_console, profile.as_deref(), &platform)?;
+    Ok(ini_chain)
+}
+
+fn launcher_platform(params: &EclipseParams) -> Platform<'_> {
+    Platform::with_overrides(
+        params.os.as_deref(),
+        params.arch.as_deref(),
+        params.ws.as_deref(),
+    )
+}
 
 /// Returns all JVM paramters needed to start the framework. This will take
 /// the given `vm_args` from command line into account as well as the parameters
-/// specified in ini files. If `params.append_vmargs` is `false` only the VM arguments
-/// from `vm_args` are returned (except if `vm_args` is empty, in this case the arguments
+/// specified in the ini files of the `ini_chain`. If `params.append_vmargs` is `false`
+/// only the VM arguments from `vm_args` are returned (except if `vm_args` is empty, in this case the arguments
 /// from ini file(s) are taken). If `params.append_vmargs` is `true` the vm args are read from
 /// ini file(s) and the `vm_args` are appended.
+///
+/// In both cases the VM arguments of environment variables are put in front, see
+/// `vm_args_from_env`. If `-debug` is set, each argument is printed along with its origin.
+/// If a profile is selected (see `select_profile`), the VM arguments of its ini sections
+/// are read and the profile name is passed to Java via the `eclipse.launcher.profile`
+/// system property.
+/// Arguments overridden by later arguments are removed, see `dedup_vm_args`.
 pub fn complete_vm_args<'a, 'b, S: AsRef<str>>(
     vm_args: &'a [S],
     params: &'a EclipseParams,
     program: &'a Path,
     win_console: bool,
+    launch_mode: &JvmLaunchMode,
+    ini_chain: &IniChain,
 ) -> Result<Vec<Cow<'b, str>>, EclipseLibErr> where 'a : 'b {
     let mut result: Vec<Cow<'b,str>> = Vec::new();
+    result.extend(vm_args_from_env(params, launch_mode).into_iter().map(Cow::from));
+    let profile = select_profile(params.profile.clone());
+    if let Some(profile) = &profile {
+        result.push(format!("{}={}", PROFILE_PROPERTY, profile).into());
+    }
 
     // VM args from command line
     let vm_args_iter = vm_args.iter().map(AsRef::as_ref).map(Cow::from);
     let vm_args_present = !vm_args.is_empty();
+    if params.debug.is_set() {
+        for vm_arg in vm_args {
+            eprintln!("VM argument {} (command line)", vm_arg.as_ref());
+        }
+    }
 
     // If we have command line VM args and ini vm args are not appended,
     // take command line VM args
-    // So we do not need to parse ini files if we don't have to.
     if !params.append_vmargs && vm_args_present {
         result.extend(vm_args_iter);
-        return Ok(result);
+        return Ok(dedup_vm_args(result));
     }
 
     // shortcut to transform an IntoIterator<Item = String> into an Iterator<Cow<'_,str>>
@@ -59,13 +117,18 @@ pub fn complete_vm_args<'a, 'b, S: AsRef<str>>(
         ($e:expr) => ( $e.into_iter().map(|s| s.into()) )
     }
 
-    // Read VM args from ini file(s)
-    if cfg!(target_os = "macos") {
-        let ini_params = vm_args_from_launcher_ini_from_config(params, program);
-        result.extend(to_cows!(ini_params));
-    }
-    let config_vm_args_iter = to_cows!(vm_args_from_config(params, program, win_console));
-    result.extend(config_vm_args_iter);
+    // Read VM args from ini file(s), user configuration for protected installs last,
+    // so it overrides the configuration of the installation
+    result.extend(to_cows!(vm_args_from_config(params, ini_chain)));
+    let ini_params = vm_args_from_launcher_ini_from_config(
+        params,
+        program,
+        win_console,
+        ini_chain,
+        profile.as_deref(),
+        &launcher_platform(params),
+    )?;
+    result.extend(to_cows!(ini_params));
 
     // Add VM args from command-line (may be empty)
     if vm_args_present {
@@ -73,60 +136,307 @@ pub fn complete_vm_args<'a, 'b, S: AsRef<str>>(
     }
     // TODO: on mac add -Xdock:icon -> APP_ICON_<pid> and -Xdock:name -> APP_NAME_<pid>, set env variables for app-name and app icon
 
-    Ok(result)
+    Ok(dedup_vm_args(result))
 }
 
+/// If the installation is protected via `-protect base`, users cannot modify its ini file.
+/// In this case the VM arguments of a per-user ini file are merged, see
+/// `get_launcher_file_path_from_configuration` for its location. On Linux this is the
+/// `IniLayer::User` file of the configuration chain, so it is not read again if the
+/// chain already contains it. The file is read like the files of the chain, with the
+/// VM arguments of the `profile` section and conditional blocks evaluated for `platform`.
 #[inline] // only called in one place, won't lead to code bloat.
-#[cfg(target_os = "macos")]
-fn vm_args_from_launcher_ini_from_config(params: &EclipseParams, program: &Path) -> Vec<String> {
+fn vm_args_from_launcher_ini_from_config(
+    params: &EclipseParams,
+    program: &Path,
+    win_console: bool,
+    ini_chain: &IniChain,
+    profile: Option<&str>,
+    platform: &Platform,
+) -> Result<Vec<String>, EclipseLibErr> {
     // Unfortunately this functionality is not documented, see:
     // https://bugs.eclipse.org/bugs/show_bug.cgi?id=509087
     // and
     // https://help.eclipse.org/index.jsp?topic=%2Forg.eclipse.platform.doc.isv%2Freference%2Fmisc%2Flauncher.html&cp=2_1_5_1
-    if !params.protect.as_ref().map_or(false, |s| s == "base") {
-        return Vec::new();
+    if params.protect.as_deref() != Some(PROTECT_BASE) {
+        return Ok(Vec::new());
     }
 
-    let ini_path = if let Some(ini) = get_launcher_file_path_from_configuration(program) {
-        ini
-    } else {
-        return Vec::new();
+    let ini_path = match get_launcher_file_path_from_configuration(program, win_console) {
+        Some(ini_path) => ini_path,
+        None => return Ok(Vec::new()),
     };
-
-    if let Some(lines_iter) = read_ini_lines(ini_path).ok() {
-        vm_args_from_params(lines_iter).collect()
-    } else {
-        Vec::new()
+    if ini_chain.files().iter().any(|file| file.path == ini_path) {
+        return Ok(Vec::new());
     }
+    let launcher_dir = program.parent().unwrap_or_else(|| Path::new(""));
+    vm_args_from_ini_file(ini_path, launcher_dir, profile, platform, params.debug.is_set())
 }
 
+/// Returns the location of the per-user ini file for protected installations:
+/// `~/Library/Application Support/<product>/<product>.ini` on macOS.
 #[cfg(target_os = "macos")]
-fn get_launcher_file_path_from_configuration(program: &Path) -> Option<PathBuf> {
-    let mut program_name = program.file_stem()?.to_str()?.to_string();
-    program_name.push_str(".ini");
-    // TODO: implement fn get_folder_for_application_data() -> Option<PathBuf>
-    // let mut ini_path = get_folder_for_application_data();
-    // ini_path.push(program_name);
-    // Ok(ini_path)
-    unimplemented!();
+fn get_launcher_file_path_from_configuration(
+    program: &Path,
+    _win_console: bool,
+) -> Option<PathBuf> {
+    let product = program.file_stem()?;
+    let mut ini_name = PathBuf::from(product);
+    ini_name.set_extension("ini");
+    let mut ini_path = get_folder_for_application_data()?;
+    ini_path.push(product);
+    ini_path.push(ini_name);
+    Some(ini_path)
 }
 
-/// This method will never be called and is only here to make conditional compilation
-/// work in complete_vm_args
+/// Returns the location of the per-user ini file for protected installations:
+/// `$XDG_CONFIG_HOME/<product>/<product>.ini` on Linux. Not supported on Windows.
 #[cfg(not(target_os = "macos"))]
-fn vm_args_from_launcher_ini_from_config(_params: &EclipseParams, _program: &Path) -> Vec<String> {
-    unimplemented!();
+fn get_launcher_file_path_from_configuration(
+    program: &Path,
+    win_console: bool,
+) -> Option<PathBuf> {
+    user_ini_path(program, win_console)
+}
+
+#[cfg(target_os = "macos")]
+fn get_folder_for_application_data() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").filter(|home| !home.is_empty())?;
+    Some(Path::new(&home).join("Library").join("Application Support"))
 }
 
-fn vm_args_from_config(params: &EclipseParams, program: &Path, win_console: bool) -> Vec<String> {
-    if let Ok(lines_iter) = read_ini(&params.ini, program, win_console) {
-        let vm_args_iter = vm_args_from_params(lines_iter);
-        vm_args_iter.collect()
+/// Reads the VM arguments of the ini file at `ini_path`, see `read_ini_file`. Returns an
+/// empty vector if the file does not exist or cannot be read. If `debug` is `true`, each
+/// argument is printed along with the file it was read from.
+fn vm_args_from_ini_file(
+    ini_path: PathBuf,
+    launcher_dir: &Path,
+    profile: Option<&str>,
+    platform: &Platform,
+    debug: bool,
+) -> Result<Vec<String>, EclipseLibErr> {
+    let ini_file = match read_ini_file(IniLayer::User, ini_path, launcher_dir, profile, platform)? {
+        Some(ini_file) => ini_file,
+        None => return Ok(Vec::new()),
+    };
+    if debug {
+        for vm_arg in ini_file.vm_args() {
+            eprintln!(
+                "VM argument {} (protected install user config: {})",
+                vm_arg.value,
+                vm_arg.path.display()
+            );
+        }
+    }
+    Ok(ini_file.vm_args().map(|vm_arg| vm_arg.value.clone()).collect())
+}
+
+/// Reads VM arguments from environment variables, using the same tokenization rules as the
+/// java executable. `ECLIPSE_VMARGS` is always read, `JDK_JAVA_OPTIONS` only if the JVM is
+/// started via JNI. The java executable reads `JDK_JAVA_OPTIONS` by itself.
+fn vm_args_from_env(params: &EclipseParams, launch_mode: &JvmLaunchMode) -> Vec<String> {
+    let mut variables = Vec::new();
+    if let JvmLaunchMode::LaunchJni { .. } = launch_mode {
+        variables.push(JDK_JAVA_OPTIONS);
+    }
+    variables.push(ECLIPSE_VMARGS);
+
+    let mut result = Vec::new();
+    for variable in variables {
+        let value = match std::env::var(variable) {
+            Ok(value) if !value.trim().is_empty() => value,
+            _ => continue,
+        };
+        if variable == JDK_JAVA_OPTIONS {
+            // Same note as printed by the java executable
+            eprintln!("NOTE: Picked up {}: {}", JDK_JAVA_OPTIONS, value);
+        }
+        for vm_arg in split_java_args(&value) {
+            if params.debug.is_set() {
+                eprintln!("VM argument {} (environment variable {})", vm_arg, variable);
+            }
+            result.push(vm_arg);
+        }
+    }
+    result
+}
+
+/// Returns the VM arguments of the system, user and product ini files in `ini_chain`.
+/// If `-debug` is set, each argument is printed along with the file it was read from.
+fn vm_args_from_config(params: &EclipseParams, ini_chain: &IniChain) -> Vec<String> {
+    let vm_args = ini_chain.vm_args();
+    if params.debug.is_set() {
+        for vm_arg in &vm_args {
+            eprintln!(
+                "VM argument {} ({:?}: {})",
+                vm_arg.value,
+                vm_arg.layer,
+                vm_arg.path.display()
+            );
+        }
+    }
+    vm_args.iter().map(|vm_arg| vm_arg.value.to_string()).collect()
+}
+
+/// Removes VM arguments that are overridden by a later argument with the same key, so the
+/// JVM gets a well defined value, regardless of the launch mode. Keys are determined for
+/// memory sizes (`-Xmx`, `-Xms`, `-Xss`), system properties (`-D<name>`), advanced options
+/// (`-XX:[+-]<name>` and `-XX:<name>=`) and `--add-opens` targets (which are only duplicates
+/// if equal). Other arguments are kept. Each removed argument is reported on stderr.
+fn dedup_vm_args(vm_args: Vec<Cow<'_, str>>) -> Vec<Cow<'_, str>> {
+    // `--add-opens <target>` spans two arguments, so we look at groups of arguments
+    let mut groups: Vec<Vec<Cow<'_, str>>> = Vec::with_capacity(vm_args.len());
+    let mut vm_args_iter = vm_args.into_iter();
+    while let Some(vm_arg) = vm_args_iter.next() {
+        let mut group = vec![vm_arg];
+        if group[0] == ADD_OPENS {
+            group.extend(vm_args_iter.next());
+        }
+        groups.push(group);
+    }
+
+    let keys: Vec<Option<String>> = groups.iter().map(|group| dedup_key(group)).collect();
+    let mut last_index_by_key = HashMap::<&str, usize>::new();
+    for (index, key) in keys.iter().enumerate() {
+        if let Some(key) = key {
+            last_index_by_key.insert(key, index);
+        }
+    }
+
+    let mut result = Vec::with_capacity(groups.len());
+    for (index, group) in groups.iter().enumerate() {
+        let last_index = keys[index].as_ref().map(|key| last_index_by_key[key.as_str()]);
+        match last_index {
+            Some(last_index) if last_index != index => eprintln!(
+                "VM argument {} dropped, overridden by {}",
+                group.join(" "),
+                groups[last_index].join(" ")
+            ),
+            _ => result.extend(group.iter().cloned()),
+        }
+    }
+    result
+}
+
+/// Returns the key of a group of VM arguments, if later arguments with the same key override it.
+fn dedup_key(group: &[Cow<'_, str>]) -> Option<String> {
+    let first = group.first()?;
+    if let Some(target) = first.strip_prefix(ADD_OPENS) {
+        // normalize --add-opens=<target> and --add-opens <target>
+        let target = match target.strip_prefix('=') {
+            Some(target) => target,
+            None if target.is_empty() => group.get(1)?,
+            None => return None,
+        };
+        return Some(format!("{}={}", ADD_OPENS, target));
+    }
+    if is_overriding_vm_arg(first) {
+        Some(vm_arg_key(first))
     } else {
-        Vec::new()
+        None
     }
 }
 
-fn vm_args_from_params(iter: impl Iterator<Item = String>) -> impl Iterator<Item = String> {
-    iter.skip_while(|s| s != VM_ARGS_PARAM).skip(1)
+#[cfg(test)]
+mod test {
+    use super::{dedup_vm_args, vm_args_from_ini_file, vm_args_from_launcher_ini_from_config};
+    use crate::params::EclipseParams;
+    use eclipse_common::compile_params::Platform;
+    use eclipse_common::ini_reader::read_ini_chain;
+    use std::borrow::Cow;
+    use std::path::Path;
+
+    fn cows(args: &[&'static str]) -> Vec<Cow<'static, str>> {
+        args.iter().map(|s| Cow::from(*s)).collect()
+    }
+
+    #[test]
+    fn test_dedup_vm_args_later_wins() {
+        let args = cows(&[
+            "-Xmx1g",
+            "-Dosgi.instance.area=/a",
+            "-XX:+UseG1GC",
+            "-Dfoo",
+            "-Xmx4g",
+            "-XX:-UseG1GC",
+            "-Dosgi.instance.area=/b",
+        ]);
+        let expected = cows(&["-Dfoo", "-Xmx4g", "-XX:-UseG1GC", "-Dosgi.instance.area=/b"]);
+        assert_eq!(dedup_vm_args(args), expected);
+    }
+
+    #[test]
+    fn test_dedup_vm_args_keeps_cumulative() {
+        let args = cows(&[
+            "-Xms256m",
+            "-Xmso1m",
+            "-XX:CompileCommand=exclude,java/lang/String.indexOf",
+            "-XX:CompileCommand=quiet",
+            "-Xms512m",
+        ]);
+        let expected = cows(&[
+            "-Xmso1m",
+            "-XX:CompileCommand=exclude,java/lang/String.indexOf",
+            "-XX:CompileCommand=quiet",
+            "-Xms512m",
+        ]);
+        assert_eq!(dedup_vm_args(args), expected);
+    }
+
+    #[test]
+    fn test_dedup_vm_args_add_opens() {
+        let args = cows(&[
+            "--add-opens",
+            "java.base/java.lang=ALL-UNNAMED",
+            "--add-opens=java.base/java.io=ALL-UNNAMED",
+            "-javaagent:a.jar",
+            "--add-opens=java.base/java.lang=ALL-UNNAMED",
+            "-javaagent:a.jar",
+        ]);
+        let expected = cows(&[
+            "--add-opens=java.base/java.io=ALL-UNNAMED",
+            "-javaagent:a.jar",
+            "--add-opens=java.base/java.lang=ALL-UNNAMED",
+            "-javaagent:a.jar",
+        ]);
+        assert_eq!(dedup_vm_args(args), expected);
+    }
+
+    #[test]
+    fn test_vm_args_from_ini_file() {
+        let dir_name = format!("eclipse_user_ini_test_{}", std::process::id());
+        let dir = std::env::temp_dir().join(dir_name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("gc.ini"), "-XX:+UseG1GC\n").unwrap();
+        let path = dir.join("eclipse.ini");
+        let ini = "-vm\n/opt/jdk\n-vmargs\n-Xmx4g\n-Dfoo=${ini.dir}\n--launcher.include\ngc.ini\n\
+                   [profile:big]\n-vmargs\n-Xmx8g\n";
+        std::fs::write(&path, ini).unwrap();
+        let platform = Platform::with_overrides(None, None, None);
+        let launcher_dir = Path::new("/opt/eclipse");
+        let profile = Some("big");
+        let vm_args = vm_args_from_ini_file(path.clone(), launcher_dir, profile, &platform, false);
+        let _ = std::fs::remove_dir_all(&dir);
+        let foo = format!("-Dfoo={}", dir.display());
+        assert_eq!(vm_args.unwrap(), vec!["-Xmx4g", &foo, "-XX:+UseG1GC", "-Xmx8g"]);
+        let vm_args = vm_args_from_ini_file(path, launcher_dir, None, &platform, false);
+        assert!(vm_args.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_launcher_ini_from_config_requires_protect_base() {
+        let program = Path::new("/nonexistent/eclipse/eclipse");
+        let platform = Platform::with_overrides(None, None, None);
+        let ini_chain = read_ini_chain(&None, program, false, None, &platform).unwrap();
+        let params = EclipseParams::default();
+        let vm_args = vm_args_from_launcher_ini_from_config(
+            &params,
+            program,
+            false,
+            &ini_chain,
+            None,
+            &platform,
+        );
+        assert!(vm_args.unwrap().is_empty());
+    }
 }
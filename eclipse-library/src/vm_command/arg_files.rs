@@ -0,0 +1,211 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides the function `expand_arg_files`, which replaces Java
+//! argument files (`@<file>`) by their content, like the `java` executable does.
+//! This is needed when starting the JVM via JNI, since `JNI_CreateJavaVM` does not
+//! know about argument files. The rules of the JDK are applied:
+//! * Arguments in the file are separated by whitespace, `#` starts a comment line.
+//! * Arguments can be quoted with `"` or `'`, also partially (`-Dfoo="a b"`). Inside of
+//!   quotes `\` escapes the next character, `\n`, `\r`, `\t` and `\f` are supported. A
+//!   `\` at the end of a line continues the quoted argument on the next line, leading
+//!   whitespace of the next line is skipped.
+//! * Argument files are not expanded recursively.
+//! * `@@<text>` is replaced by `@<text>` without reading a file.
+//! * After `--disable-@files` no further argument files are expanded.
+//!
+//! Argument files are decoded as UTF-8, a leading byte order mark is ignored.
+
+use crate::errors::EclipseLibErr;
+use std::borrow::Cow;
+use std::iter::Peekable;
+use std::path::PathBuf;
+use std::str::Chars;
+
+const ARG_FILE_PREFIX: char = '@';
+const DISABLE_ARG_FILES: &str = "--disable-@files";
+const BYTE_ORDER_MARK: char = '\u{feff}';
+
+/// Replaces all argument files in `vm_args` by the arguments they contain. See
+/// module documentation for details. Returns an error if an argument file cannot be read.
+pub fn expand_arg_files<'a>(
+    vm_args: Vec<Cow<'a, str>>,
+) -> Result<Vec<Cow<'a, str>>, EclipseLibErr> {
+    let mut result = Vec::with_capacity(vm_args.len());
+    let mut vm_args = vm_args.into_iter();
+    while let Some(arg) = vm_args.next() {
+        if arg == DISABLE_ARG_FILES {
+            // Only understood by the java executable, so it is not passed on
+            result.extend(vm_args);
+            break;
+        }
+        let file_name = match arg.strip_prefix(ARG_FILE_PREFIX) {
+            Some(file_name) if !file_name.is_empty() => file_name,
+            _ => {
+                result.push(arg);
+                continue;
+            }
+        };
+        if file_name.starts_with(ARG_FILE_PREFIX) {
+            // escaped @ prefix
+            result.push(file_name.to_string().into());
+            continue;
+        }
+        let path = PathBuf::from(file_name);
+        let content = std::fs::read(&path).map_err(|e| EclipseLibErr::ArgFileReadFail(path, e))?;
+        let content = String::from_utf8_lossy(&content);
+        let content = content.trim_start_matches(BYTE_ORDER_MARK);
+        result.extend(split_java_args(content).into_iter().map(Cow::from));
+    }
+    Ok(result)
+}
+
+/// Splits the `content` of an argument file into arguments. The same rules are
+/// applied by the java executable to the `JDK_JAVA_OPTIONS` environment variable.
+pub fn split_java_args(content: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut chars = content.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if is_whitespace(c) {
+            chars.next();
+        } else if c == '#' {
+            // comment until end of line
+            while matches!(chars.peek(), Some(c) if !is_line_break(*c)) {
+                chars.next();
+            }
+        } else {
+            args.push(parse_arg(&mut chars));
+        }
+    }
+    args
+}
+
+/// Reads the argument starting at the current position of `chars`.
+fn parse_arg(chars: &mut Peekable<Chars<'_>>) -> String {
+    let mut arg = String::new();
+    let mut quote: Option<char> = None;
+    while let Some(&c) = chars.peek() {
+        match quote {
+            None if is_whitespace(c) => break,
+            // quoted arguments cannot span lines, unless escaped
+            Some(_) if is_line_break(c) => break,
+            None => {
+                chars.next();
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                } else {
+                    arg.push(c);
+                }
+            }
+            Some(quote_char) => {
+                chars.next();
+                if c == quote_char {
+                    quote = None;
+                } else if c == '\\' {
+                    parse_escape(chars, &mut arg);
+                } else {
+                    arg.push(c);
+                }
+            }
+        }
+    }
+    arg
+}
+
+/// Handles the character following a `\` inside of quotes.
+fn parse_escape(chars: &mut Peekable<Chars<'_>>, arg: &mut String) {
+    match chars.next() {
+        Some('n') => arg.push('\n'),
+        Some('r') => arg.push('\r'),
+        Some('t') => arg.push('\t'),
+        Some('f') => arg.push('\u{c}'),
+        Some(c) if is_line_break(c) => {
+            // line continuation, skip line break and leading whitespace of the next line
+            if c == '\r' && chars.peek() == Some(&'\n') {
+                chars.next();
+            }
+            while matches!(chars.peek(), Some(c) if is_whitespace(*c) && !is_line_break(*c)) {
+                chars.next();
+            }
+        }
+        Some(c) => arg.push(c),
+        None => {}
+    }
+}
+
+fn is_whitespace(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\u{c}' || is_line_break(c)
+}
+
+fn is_line_break(c: char) -> bool {
+    c == '\n' || c == '\r'
+}
+
+#[cfg(test)]
+mod test {
+    use super::{expand_arg_files, split_java_args};
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_split_java_args() {
+        let content = "# comment\n--add-opens java.base/java.lang=ALL-UNNAMED\r\n  -Xmx1g\t-cp C:\\lib\\a.jar\n";
+        let expected = vec![
+            "--add-opens",
+            "java.base/java.lang=ALL-UNNAMED",
+            "-Xmx1g",
+            "-cp",
+            "C:\\lib\\a.jar",
+        ];
+        assert_eq!(split_java_args(content), expected);
+    }
+
+    #[test]
+    fn test_parse_arg_file_quotes() {
+        let content = "-Dfoo=\"a b\" 'c#d' \"e\\tf\\\"\" \"g\\\n    h\" -Dx=\"unterminated\nnext";
+        let expected = vec![
+            "-Dfoo=a b",
+            "c#d",
+            "e\tf\"",
+            "gh",
+            "-Dx=unterminated",
+            "next",
+        ];
+        assert_eq!(split_java_args(content), expected);
+    }
+
+    #[test]
+    fn test_expand_arg_files() {
+        let path =
+            std::env::temp_dir().join(format!("eclipse_argfile_test_{}", std::process::id()));
+        std::fs::write(&path, "\u{feff}-Dfoo=bar @nested\n").unwrap();
+        let arg_file = format!("@{}", path.display());
+        let args: Vec<Cow<'_, str>> = vec![
+            "-Xmx1g".into(),
+            arg_file.clone().into(),
+            "@@literal".into(),
+            "--disable-@files".into(),
+            arg_file.clone().into(),
+        ];
+        let expanded = expand_arg_files(args);
+        let _ = std::fs::remove_file(&path);
+        let expected: Vec<Cow<'_, str>> = vec![
+            "-Xmx1g".into(),
+            "-Dfoo=bar".into(),
+            "@nested".into(),
+            "@literal".into(),
+            arg_file.into(),
+        ];
+        assert_eq!(expanded.unwrap(), expected);
+    }
+}
@@ -0,0 +1,379 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides the function `detect_jvm_info`, which determines vendor
+//! and version of the JVM that is about to be launched.
+
+use crate::vm_lookup::JvmLaunchMode;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const RELEASE_FILE: &str = "release";
+const RELEASE_JAVA_VERSION: &str = "JAVA_VERSION";
+const RELEASE_JVM_VARIANT: &str = "JVM_VARIANT";
+const RELEASE_IMPLEMENTOR: &str = "IMPLEMENTOR";
+
+/// The `release` file is located in the JDK/JRE home folder. Starting from
+/// the VM executable or library we look at most this many levels up.
+/// (e.g. `<home>/jre/lib/amd64/server/libjvm.so` on Java 8)
+const MAX_RELEASE_FILE_DEPTH: usize = 5;
+
+/// The virtual machine implementation of a JVM
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum JvmVendor {
+    /// Oracle/Sun HotSpot VM, also used by OpenJDK builds
+    HotSpot,
+    /// Eclipse OpenJ9 or IBM J9 VM
+    OpenJ9,
+    Unknown,
+}
+
+/// Information about a JVM. Fields that could not be determined
+/// hold `JvmVendor::Unknown` or `None`.
+#[derive(Debug, Eq, PartialEq)]
+pub struct JvmInfo {
+    pub vendor: JvmVendor,
+
+    /// The major Java version, e.g. `7` for version "1.7.0_80" or `11` for "11.0.2"
+    pub major_version: Option<u32>,
+
+    /// The update of Java versions prior to 9, e.g. `80` for version "1.7.0_80"
+    pub update_version: Option<u32>,
+}
+
+impl Default for JvmInfo {
+    fn default() -> Self {
+        JvmInfo {
+            vendor: JvmVendor::Unknown,
+            major_version: None,
+            update_version: None,
+        }
+    }
+}
+
+impl JvmInfo {
+    /// Returns `true` if the JVM is known to have a permanent generation,
+    /// which can be sized via `-XX:MaxPermSize`.
+    pub fn supports_perm_gen(&self) -> bool {
+        self.vendor == JvmVendor::HotSpot && matches!(self.major_version, Some(v) if v < 8)
+    }
+
+    /// Returns `true` if the JVM is known to warn about, or refuse to start
+    /// with the `-XX:MaxPermSize` option.
+    pub fn rejects_perm_gen(&self) -> bool {
+        self.vendor == JvmVendor::OpenJ9 || matches!(self.major_version, Some(v) if v >= 8)
+    }
+
+    /// Returns how the JVM can be made to exit on an `OutOfMemoryError`.
+    /// VMs of unknown vendor are assumed to be HotSpot based.
+    pub fn exit_on_oom(&self) -> ExitOnOom {
+        match self.vendor {
+            JvmVendor::OpenJ9 => ExitOnOom::DumpFile,
+            _ if self.supports_exit_on_oom_error() => ExitOnOom::ExitCode(EXIT_ON_OOM_ERROR, 3),
+            _ => ExitOnOom::Unsupported,
+        }
+    }
+
+    /// Returns `true` if the JVM is known to support `-XX:+ExitOnOutOfMemoryError`,
+    /// which was introduced with Java 8u92. Older VMs refuse to start with this option.
+    fn supports_exit_on_oom_error(&self) -> bool {
+        match (self.major_version, self.update_version) {
+            (Some(major), _) if major > 8 => true,
+            (Some(8), Some(update)) => update >= 92,
+            _ => false,
+        }
+    }
+}
+
+/// How a JVM can be made to exit on an `OutOfMemoryError`, see `JvmInfo::exit_on_oom`
+#[derive(Debug, Eq, PartialEq)]
+pub enum ExitOnOom {
+    /// The VM argument, which makes the JVM terminate with the given exit code.
+    /// The exit code is not used by the JVM otherwise.
+    ExitCode(&'static str, i32),
+
+    /// The exit dump agent terminates the JVM with exit code `OPENJ9_OOM_EXIT_CODE`, which
+    /// is also used for other failures. The VM arguments returned by `openj9_exit_on_oom_args`
+    /// additionally write a java dump to a file, which tells an `OutOfMemoryError` apart.
+    DumpFile,
+
+    /// The JVM is too old or its version is unknown, so it may not support `EXIT_ON_OOM_ERROR`
+    Unsupported,
+}
+
+/// The HotSpot VM argument making the JVM exit on an `OutOfMemoryError`
+pub const EXIT_ON_OOM_ERROR: &str = "-XX:+ExitOnOutOfMemoryError";
+
+/// The exit code of OpenJ9's exit dump agent
+pub const OPENJ9_OOM_EXIT_CODE: i32 = 1;
+
+/// Returns the OpenJ9 VM arguments writing a java dump to `dump_file` and then terminating
+/// the JVM on an `OutOfMemoryError`. The exit agent has the lowest priority, so it runs
+/// after the java dump is written.
+pub fn openj9_exit_on_oom_args(dump_file: &Path) -> Vec<String> {
+    const OOM_EVENT: &str = "events=systhrow,filter=java/lang/OutOfMemoryError";
+    vec![
+        format!("-Xdump:java:{},file={}", OOM_EVENT, dump_file.display()),
+        format!("-Xdump:exit:{}", OOM_EVENT),
+    ]
+}
+
+/// Provides the `JvmInfo` of a JVM, which is only detected on first access and then
+/// reused, since detection may involve starting `java -version`.
+pub struct LazyJvmInfo<'a> {
+    launch_mode: &'a JvmLaunchMode,
+    info: Option<JvmInfo>,
+}
+
+impl<'a> LazyJvmInfo<'a> {
+    pub fn new(launch_mode: &'a JvmLaunchMode) -> LazyJvmInfo<'a> {
+        LazyJvmInfo {
+            launch_mode,
+            info: None,
+        }
+    }
+
+    /// Returns the information of the JVM, detecting it if not done yet
+    pub fn get(&mut self) -> &JvmInfo {
+        let launch_mode = self.launch_mode;
+        self.info
+            .get_or_insert_with(|| detect_jvm_info(launch_mode))
+    }
+}
+
+/// Determines vendor and version of the JVM described by `launch_mode`.
+/// The `release` file of the Java installation is consulted first. If it
+/// cannot be found and the VM is launched as an executable, the output of
+/// `java -version` is inspected.
+pub fn detect_jvm_info(launch_mode: &JvmLaunchMode) -> JvmInfo {
+    let vm_path = match launch_mode {
+        JvmLaunchMode::LaunchJni { jni_lib, .. } => jni_lib,
+        JvmLaunchMode::LaunchExe { exe, .. } => exe,
+    };
+    let release_content = find_release_file(vm_path).and_then(|p| std::fs::read_to_string(p).ok());
+    if let Some(content) = release_content {
+        return parse_release_file(&content);
+    }
+    if let JvmLaunchMode::LaunchExe { exe, .. } = launch_mode {
+        // java prints version information to stderr
+        if let Ok(output) = Command::new(exe).arg("-version").output() {
+            let version_text = String::from_utf8_lossy(&output.stderr);
+            return parse_version_output(&version_text);
+        }
+    }
+    JvmInfo::default()
+}
+
+fn find_release_file(vm_path: &Path) -> Option<PathBuf> {
+    vm_path
+        .ancestors()
+        .skip(1)
+        .take(MAX_RELEASE_FILE_DEPTH)
+        .map(|dir| dir.join(RELEASE_FILE))
+        .find(|file| file.is_file())
+}
+
+/// Parses the content of a Java installation's `release` file, which
+/// consists of lines in the form `KEY="value"`.
+fn parse_release_file(content: &str) -> JvmInfo {
+    let value_of = |key: &str| -> Option<&str> {
+        content
+            .lines()
+            .filter_map(|line| {
+                let mut split = line.splitn(2, '=');
+                Some((split.next()?.trim(), split.next()?))
+            })
+            .find(|(line_key, _)| *line_key == key)
+            .map(|(_, value)| value.trim().trim_matches('"'))
+    };
+    let version = value_of(RELEASE_JAVA_VERSION);
+    let major_version = version.and_then(parse_major_version);
+    let is_j9 = matches!(value_of(RELEASE_JVM_VARIANT), Some(v) if v.to_lowercase().contains("j9"))
+        || matches!(value_of(RELEASE_IMPLEMENTOR), Some(v) if v.contains("IBM"));
+    let vendor = if is_j9 {
+        JvmVendor::OpenJ9
+    } else if major_version.is_some() {
+        JvmVendor::HotSpot
+    } else {
+        JvmVendor::Unknown
+    };
+    JvmInfo {
+        vendor,
+        major_version,
+        update_version: version.and_then(parse_update_version),
+    }
+}
+
+/// Parses the output of `java -version`, where the first line
+/// contains the quoted version, e.g. `java version "1.7.0_80"`, and
+/// the following lines name the VM implementation.
+fn parse_version_output(output: &str) -> JvmInfo {
+    let version = output
+        .lines()
+        .next()
+        .and_then(|first_line| first_line.split('"').nth(1));
+    let major_version = version.and_then(parse_major_version);
+    let vendor = if output.contains("OpenJ9") || output.contains("IBM J9") {
+        JvmVendor::OpenJ9
+    } else if output.contains("HotSpot") || output.contains("OpenJDK") {
+        JvmVendor::HotSpot
+    } else {
+        JvmVendor::Unknown
+    };
+    JvmInfo {
+        vendor,
+        major_version,
+        update_version: version.and_then(parse_update_version),
+    }
+}
+
+/// Parses the major version of a Java version string. Versions prior to
+/// Java 9 have the form "1.<major>.0_<update>", later versions the form
+/// "<major>.<minor>.<security>" (where minor and security may be omitted).
+fn parse_major_version(version: &str) -> Option<u32> {
+    let mut components = version.split(|c: char| !c.is_ascii_digit());
+    let first: u32 = components.next()?.parse().ok()?;
+    if first == 1 {
+        components.next()?.parse().ok()
+    } else {
+        Some(first)
+    }
+}
+
+/// Parses the update of a Java version string prior to Java 9, e.g. `92` for
+/// "1.8.0_92-b14". The initial release "1.8.0" has update `0`. Returns `None`
+/// for later versions, which have no update component.
+fn parse_update_version(version: &str) -> Option<u32> {
+    if !version.starts_with("1.") {
+        return None;
+    }
+    let update = match version.split_once('_') {
+        Some((_, update)) => update.split(|c: char| !c.is_ascii_digit()).next()?,
+        None => "0",
+    };
+    update.parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_major_version_legacy() {
+        assert_eq!(parse_major_version("1.7.0_80"), Some(7));
+        assert_eq!(parse_major_version("1.8.0_242"), Some(8));
+    }
+
+    #[test]
+    fn test_parse_major_version_modern() {
+        assert_eq!(parse_major_version("11.0.2"), Some(11));
+        assert_eq!(parse_major_version("14"), Some(14));
+        assert_eq!(parse_major_version("15-ea"), Some(15));
+    }
+
+    #[test]
+    fn test_parse_update_version() {
+        assert_eq!(parse_update_version("1.8.0_92-b14"), Some(92));
+        assert_eq!(parse_update_version("1.7.0_80"), Some(80));
+        assert_eq!(parse_update_version("1.8.0"), Some(0));
+        assert_eq!(parse_update_version("11.0.2"), None);
+    }
+
+    #[test]
+    fn test_parse_major_version_invalid() {
+        assert_eq!(parse_major_version(""), None);
+        assert_eq!(parse_major_version("unknown"), None);
+    }
+
+    #[test]
+    fn test_parse_version_output_oracle_7() {
+        let output = r#"java version "1.7.0_80"
+Java(TM) SE Runtime Environment (build 1.7.0_80-b15)
+Java HotSpot(TM) 64-Bit Server VM (build 24.80-b11, mixed mode)"#;
+        let expected = JvmInfo {
+            vendor: JvmVendor::HotSpot,
+            major_version: Some(7),
+            update_version: Some(80),
+        };
+        assert_eq!(parse_version_output(output), expected);
+    }
+
+    #[test]
+    fn test_parse_version_output_openj9_8() {
+        let output = r#"openjdk version "1.8.0_242"
+OpenJDK Runtime Environment (build 1.8.0_242-b08)
+Eclipse OpenJ9 VM (build openj9-0.18.1, JRE 1.8.0 Linux amd64-64-Bit)"#;
+        let expected = JvmInfo {
+            vendor: JvmVendor::OpenJ9,
+            major_version: Some(8),
+            update_version: Some(242),
+        };
+        assert_eq!(parse_version_output(output), expected);
+    }
+
+    #[test]
+    fn test_parse_release_file_hotspot_11() {
+        let content = "IMPLEMENTOR=\"AdoptOpenJDK\"\nJAVA_VERSION=\"11.0.6\"\nJVM_VARIANT=\"Hotspot\"\n";
+        let expected = JvmInfo {
+            vendor: JvmVendor::HotSpot,
+            major_version: Some(11),
+            update_version: None,
+        };
+        assert_eq!(parse_release_file(content), expected);
+    }
+
+    #[test]
+    fn test_parse_release_file_openj9() {
+        let content = "JAVA_VERSION=\"1.8.0_242\"\nJVM_VARIANT=\"Openj9\"\n";
+        let info = parse_release_file(content);
+        assert_eq!(info.vendor, JvmVendor::OpenJ9);
+        assert!(info.rejects_perm_gen());
+    }
+
+    #[test]
+    fn test_perm_gen_support_unknown_version() {
+        let info = JvmInfo {
+            vendor: JvmVendor::HotSpot,
+            major_version: None,
+            update_version: None,
+        };
+        assert!(!info.supports_perm_gen());
+        assert!(!info.rejects_perm_gen());
+    }
+
+    #[test]
+    fn test_exit_on_oom() {
+        let jvm_info = |vendor, major_version, update_version| JvmInfo {
+            vendor,
+            major_version,
+            update_version,
+        };
+        let exit_on_oom = ExitOnOom::ExitCode(EXIT_ON_OOM_ERROR, 3);
+        let hotspot_11 = jvm_info(JvmVendor::HotSpot, Some(11), None);
+        assert_eq!(hotspot_11.exit_on_oom(), exit_on_oom);
+        let hotspot_8u92 = jvm_info(JvmVendor::HotSpot, Some(8), Some(92));
+        assert_eq!(hotspot_8u92.exit_on_oom(), exit_on_oom);
+        let hotspot_8u91 = jvm_info(JvmVendor::HotSpot, Some(8), Some(91));
+        assert_eq!(hotspot_8u91.exit_on_oom(), ExitOnOom::Unsupported);
+        let hotspot_7 = jvm_info(JvmVendor::HotSpot, Some(7), Some(80));
+        assert_eq!(hotspot_7.exit_on_oom(), ExitOnOom::Unsupported);
+        assert_eq!(JvmInfo::default().exit_on_oom(), ExitOnOom::Unsupported);
+        let openj9 = jvm_info(JvmVendor::OpenJ9, Some(11), None);
+        assert_eq!(openj9.exit_on_oom(), ExitOnOom::DumpFile);
+        let args = openj9_exit_on_oom_args(Path::new("/tmp/dir/oom.txt"));
+        let event = "events=systhrow,filter=java/lang/OutOfMemoryError";
+        assert_eq!(args[0], format!("-Xdump:java:{},file=/tmp/dir/oom.txt", event));
+        assert_eq!(args[1], format!("-Xdump:exit:{}", event));
+    }
+}
@@ -16,27 +16,58 @@
 #[cfg_attr(target_os = "linux", path = "linux.rs")]
 #[cfg_attr(target_os = "windows", path = "windows.rs")]
 mod os;
-
-use crate::params::EclipseParams;
+mod arg_files;
+mod jvm_info;
+
+use crate::errors::EclipseLibErr;
+use crate::memory_limit::available_memory;
+use crate::oom_retry::{format_heap_size, OomExit, MAX_HEAP_ARG, MIB};
+use crate::params::{EclipseParams, HeapPercent};
+use crate::private_dir::PrivateDir;
 use crate::vm_lookup::JvmLaunchMode;
 use core::cmp::min;
 use eclipse_common::arg_parser::OptionalParam;
 use eclipse_common::eclipse_params_flags::*;
+use eclipse_common::option_util::opt_str;
 use eclipse_common::path_util::strip_unc_prefix;
+use arg_files::expand_arg_files;
+pub use arg_files::split_java_args;
+use jvm_info::{
+    openj9_exit_on_oom_args, ExitOnOom, JvmInfo, LazyJvmInfo, EXIT_ON_OOM_ERROR,
+    OPENJ9_OOM_EXIT_CODE,
+};
 use os::{default_vm_args, is_modular_vm};
 use std::borrow::Cow;
 use std::path::Path;
 
 const CLASSPATH_PREFIX: &str = "-Djava.class.path=";
 const ADDMODULES: &str = "--add-modules";
+const MAX_PERM_SIZE: &str = "-XX:MaxPermSize";
+
+/// Prefix of the directory holding the java dump OpenJ9 writes on an `OutOfMemoryError`
+const OOM_DUMP_DIR_PREFIX: &str = "eclipse_oom_";
+const OOM_DUMP_FILE: &str = "oom_javacore.txt";
+
+/// VM arguments setting the maximum heap size, which take precedence over `--launcher.heapPercent`
+const MAX_HEAP_SIZE_ARGS: &[&str] = &[
+    MAX_HEAP_ARG,
+    "-XX:MaxHeapSize=",
+    "-XX:MaxRAMPercentage=",
+    "-XX:MaxRAMFraction=",
+];
 
 #[derive(Debug)]
 pub struct VmArgs<'e> {
     pub vm_args: Vec<Cow<'e, str>>,
     pub program_args: Vec<Cow<'e, str>>,
+
+    /// How the JVM signals its termination on an `OutOfMemoryError`,
+    /// if the JVM is configured to do so (see `--launcher.oomRetry`)
+    pub oom_exit: Option<OomExit>,
 }
 
-/// Get the command and arguments to start the Java VM.
+/// Get the command and arguments to start the Java VM. Returns an error
+/// if an argument file referenced in the VM arguments cannot be read.
 pub fn get_vm_command<'a, 'b>(
     launch_mode: &'a JvmLaunchMode,
     args: &'a [&str],
@@ -45,7 +76,7 @@ pub fn get_vm_command<'a, 'b>(
     params: &'a EclipseParams,
     exitdata: &'a str,
     program_path: &'a Path,
-) -> VmArgs<'b>
+) -> Result<VmArgs<'b>, EclipseLibErr>
 where
     'a: 'b,
 {
@@ -56,6 +87,10 @@ where
     } else {
         user_vm_args.to_owned()
     };
+    // The java executable expands @argfiles itself, JNI_CreateJavaVM does not
+    if let JvmLaunchMode::LaunchJni { .. } = launch_mode {
+        vm_args = expand_arg_files(vm_args)?;
+    }
 
     // the startup jarFile goes on the classpath
     let jar_file_cow = jar_file.to_string_lossy();
@@ -71,6 +106,17 @@ where
     };
 
     adjust_vm_args(&launch_mode, &mut vm_args);
+    // detected at most once, shared by all adjustments depending on the JVM
+    let mut jvm_info = LazyJvmInfo::new(launch_mode);
+    adjust_perm_gen_args(&mut jvm_info, opt_str(&params.perm_gen), &mut vm_args);
+    if let Some(percent) = params.heap_percent {
+        add_max_heap_arg(percent, &mut vm_args);
+    }
+    let oom_exit = if params.oom_retry {
+        add_exit_on_oom_arg(launch_mode, &mut jvm_info, &mut vm_args)
+    } else {
+        None
+    };
     // if the user specified a classpath, skip it
     let classpath_pos_opt = vm_args
         .iter()
@@ -104,10 +150,11 @@ where
         launch_mode,
     );
 
-    VmArgs {
+    Ok(VmArgs {
         vm_args: result_vm_args,
         program_args: result_program_arts,
-    }
+        oom_exit,
+    })
 }
 
 fn get_program_args<'a, 'b, 'c, S: AsRef<str>>(
@@ -284,6 +331,114 @@ fn adjust_vm_args(launch_mode: &JvmLaunchMode, vm_args: &mut Vec<Cow<'_, str>>)
     }
 }
 
+/// If `perm_gen` is set (via `--launcher.XXMaxPermSize`), adds `-XX:MaxPermSize=<perm_gen>`
+/// for HotSpot VMs older than Java 8, unless the user already specified the option.
+/// For VMs that warn about or refuse to start with this option (Java 8+, OpenJ9), the
+/// option is removed from the `vm_args`.
+fn adjust_perm_gen_args(
+    jvm_info: &mut LazyJvmInfo<'_>,
+    perm_gen: Option<&str>,
+    vm_args: &mut Vec<Cow<'_, str>>,
+) {
+    // Only inspect the JVM if we have to, this may involve starting `java -version`
+    let perm_gen_specified = vm_args.iter().any(|s| is_perm_gen_arg(s));
+    if perm_gen.is_none() && !perm_gen_specified {
+        return;
+    }
+    apply_perm_gen_args(jvm_info.get(), perm_gen, vm_args);
+}
+
+fn apply_perm_gen_args(jvm_info: &JvmInfo, perm_gen: Option<&str>, vm_args: &mut Vec<Cow<'_, str>>) {
+    if jvm_info.rejects_perm_gen() {
+        vm_args.retain(|s| !is_perm_gen_arg(s));
+        return;
+    }
+    let perm_gen_specified = vm_args.iter().any(|s| is_perm_gen_arg(s));
+    if let (Some(size), false) = (perm_gen, perm_gen_specified) {
+        if jvm_info.supports_perm_gen() {
+            vm_args.push(format!("{}={}", MAX_PERM_SIZE, size).into());
+        }
+    }
+}
+
+/// Adds the VM arguments making the JVM exit on an `OutOfMemoryError`, so the launcher can
+/// restart it with a larger heap. Returns how the launcher detects this exit. This is only
+/// supported when launching the Java executable, since with JNI launching the exit would
+/// terminate the launcher process as well. If the exit cannot be configured, a warning is
+/// printed and `None` is returned.
+fn add_exit_on_oom_arg(
+    launch_mode: &JvmLaunchMode,
+    jvm_info: &mut LazyJvmInfo<'_>,
+    vm_args: &mut Vec<Cow<'_, str>>,
+) -> Option<OomExit> {
+    if let JvmLaunchMode::LaunchJni { .. } = launch_mode {
+        eprintln!(
+            "Warning: {} disabled, it only works when the Java executable is launched",
+            OOM_RETRY
+        );
+        return None;
+    }
+    match jvm_info.get().exit_on_oom() {
+        ExitOnOom::ExitCode(exit_on_oom_arg, exit_code) => {
+            if !vm_args.iter().any(|arg| arg == exit_on_oom_arg) {
+                vm_args.push(exit_on_oom_arg.into());
+            }
+            Some(OomExit::exit_code(exit_code))
+        }
+        ExitOnOom::DumpFile => {
+            let dir = match PrivateDir::create(OOM_DUMP_DIR_PREFIX) {
+                Ok(dir) => dir,
+                Err(err) => {
+                    eprintln!(
+                        "Warning: {} disabled, cannot create a temporary directory: {}",
+                        OOM_RETRY, err
+                    );
+                    return None;
+                }
+            };
+            let dump_file = dir.path().join(OOM_DUMP_FILE);
+            vm_args.extend(openj9_exit_on_oom_args(&dump_file).into_iter().map(Cow::from));
+            Some(OomExit::dump_file(OPENJ9_OOM_EXIT_CODE, dir, dump_file))
+        }
+        ExitOnOom::Unsupported => {
+            eprintln!(
+                "Warning: {} disabled, the Java VM may not support {} (requires Java 8u92+)",
+                OOM_RETRY, EXIT_ON_OOM_ERROR
+            );
+            None
+        }
+    }
+}
+
+/// Adds `-Xmx` with `percent` of the memory available to the JVM (see `memory_limit`),
+/// unless the maximum heap size is already specified in `vm_args`. Nothing is added
+/// if the available memory cannot be determined.
+fn add_max_heap_arg(percent: HeapPercent, vm_args: &mut Vec<Cow<'_, str>>) {
+    let max_heap_specified = vm_args
+        .iter()
+        .any(|arg| MAX_HEAP_SIZE_ARGS.iter().any(|prefix| arg.starts_with(prefix)));
+    if max_heap_specified {
+        return;
+    }
+    if let Some(arg) = available_memory().and_then(|available| max_heap_arg(percent, available)) {
+        vm_args.push(arg.into());
+    }
+}
+
+/// Returns the `-Xmx` argument for `percent` of the `available` memory,
+/// rounded down to whole megabytes. Returns `None` if this is less than a megabyte.
+fn max_heap_arg(percent: HeapPercent, available: u64) -> Option<String> {
+    let max_heap = available.saturating_mul(u64::from(percent.0)) / 100 / MIB * MIB;
+    if max_heap == 0 {
+        return None;
+    }
+    Some(format!("{}{}", MAX_HEAP_ARG, format_heap_size(max_heap)))
+}
+
+fn is_perm_gen_arg(arg: &str) -> bool {
+    arg.starts_with(MAX_PERM_SIZE)
+}
+
 fn remove_modular_vm_args(vm_args: &mut Vec<Cow<'_, str>>) {
     // remove --add-modules arguments
     let mut skip: usize = 0;
@@ -313,7 +468,9 @@ fn remove_modular_vm_args(vm_args: &mut Vec<Cow<'_, str>>) {
 
 #[cfg(test)]
 mod test {
-    use super::remove_modular_vm_args;
+    use super::jvm_info::{JvmInfo, JvmVendor};
+    use super::{add_max_heap_arg, apply_perm_gen_args, max_heap_arg, remove_modular_vm_args};
+    use crate::params::HeapPercent;
     use super::ADDMODULES;
     use std::borrow::Cow;
 
@@ -431,4 +588,68 @@ mod test {
         remove_modular_vm_args(&mut args);
         assert_eq!(args, expected);
     }
+
+    #[test]
+    fn test_apply_perm_gen_args_hotspot_7_added() {
+        let info = JvmInfo {
+            vendor: JvmVendor::HotSpot,
+            major_version: Some(7),
+            update_version: None,
+        };
+        let mut args: Vec<Cow<'_, str>> = vec_into!["-Xmx1g",];
+        apply_perm_gen_args(&info, Some("256m"), &mut args);
+        let expected: Vec<Cow<'_, str>> = vec_into!["-Xmx1g", "-XX:MaxPermSize=256m",];
+        assert_eq!(args, expected);
+    }
+
+    #[test]
+    fn test_apply_perm_gen_args_hotspot_7_user_specified_kept() {
+        let info = JvmInfo {
+            vendor: JvmVendor::HotSpot,
+            major_version: Some(7),
+            update_version: None,
+        };
+        let mut args: Vec<Cow<'_, str>> = vec_into!["-XX:MaxPermSize=512m",];
+        let expected = args.clone();
+        apply_perm_gen_args(&info, Some("256m"), &mut args);
+        assert_eq!(args, expected);
+    }
+
+    #[test]
+    fn test_apply_perm_gen_args_java_8_stripped() {
+        let info = JvmInfo {
+            vendor: JvmVendor::HotSpot,
+            major_version: Some(8),
+            update_version: None,
+        };
+        let mut args: Vec<Cow<'_, str>> = vec_into!["-XX:MaxPermSize=512m", "-Xmx1g",];
+        apply_perm_gen_args(&info, Some("256m"), &mut args);
+        let expected: Vec<Cow<'_, str>> = vec_into!["-Xmx1g",];
+        assert_eq!(args, expected);
+    }
+
+    #[test]
+    fn test_apply_perm_gen_args_unknown_vm_unchanged() {
+        let info = JvmInfo::default();
+        let mut args: Vec<Cow<'_, str>> = vec_into!["-XX:MaxPermSize=512m",];
+        let expected = args.clone();
+        apply_perm_gen_args(&info, Some("256m"), &mut args);
+        assert_eq!(args, expected);
+    }
+
+    #[test]
+    fn test_max_heap_arg() {
+        let gib = 1024 * 1024 * 1024;
+        assert_eq!(max_heap_arg(HeapPercent(50), 16 * gib).unwrap(), "-Xmx8g");
+        assert_eq!(max_heap_arg(HeapPercent(25), 1000 * 1024 * 1024).unwrap(), "-Xmx250m");
+        assert_eq!(max_heap_arg(HeapPercent(1), 1024 * 1024), None);
+    }
+
+    #[test]
+    fn test_add_max_heap_arg_keeps_user_setting() {
+        let mut args: Vec<Cow<'_, str>> = vec_into!["-XX:MaxRAMPercentage=75", "-XX:+UseG1GC",];
+        let expected = args.clone();
+        add_max_heap_arg(HeapPercent(50), &mut args);
+        assert_eq!(args, expected);
+    }
 }
@@ -0,0 +1,266 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides `find_crash_log`, which locates the fatal error log
+//! (`hs_err_pid<pid>.log`) a HotSpot JVM writes when it crashes, and parses
+//! its header into a `CrashLog`.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const ERROR_FILE_ARG: &str = "-XX:ErrorFile=";
+const FATAL_ERROR_HEADER: &str = "A fatal error has been detected by the Java Runtime Environment:";
+const JRE_VERSION_PREFIX: &str = "JRE version:";
+const PROBLEMATIC_FRAME_HEADER: &str = "Problematic frame:";
+
+/// Only the header of the log is parsed, it is located within the first lines
+const MAX_HEADER_LINES: usize = 40;
+
+/// Summary of a HotSpot fatal error log
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct CrashLog {
+    pub path: PathBuf,
+
+    /// The signal or exception that crashed the VM (e.g. `SIGSEGV (0xb)`),
+    /// or the error description if the VM did not crash due to a signal
+    /// (e.g. when running out of native memory).
+    pub signal: Option<String>,
+
+    /// The frame the crash occurred in (e.g. `C  [libc.so.6+0x18b9f5]`)
+    pub problematic_frame: Option<String>,
+    pub java_version: Option<String>,
+}
+
+impl fmt::Display for CrashLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(signal) = &self.signal {
+            writeln!(f, "Error: {}", signal)?;
+        }
+        if let Some(frame) = &self.problematic_frame {
+            writeln!(f, "Problematic frame: {}", frame)?;
+        }
+        if let Some(version) = &self.java_version {
+            writeln!(f, "Java version: {}", version)?;
+        }
+        write!(f, "Crash log: {}", self.path.display())
+    }
+}
+
+/// Searches the fatal error log of the crashed JVM process with the given `pid`.
+/// If `-XX:ErrorFile=<path>` is contained in `args`, only this path is considered
+/// (with `%p` replaced by the `pid`, relative to `working_dir`). Otherwise the
+/// default file `hs_err_pid<pid>.log` is searched in `working_dir` and the
+/// temporary directory. Files last modified before the JVM was started at
+/// `start_time` are left over from other processes and are ignored.
+pub(crate) fn find_crash_log<S: AsRef<str>>(
+    args: &[S],
+    pid: u32,
+    working_dir: &Path,
+    start_time: SystemTime,
+) -> Option<CrashLog> {
+    let error_file = args
+        .iter()
+        .rev()
+        .find_map(|arg| arg.as_ref().strip_prefix(ERROR_FILE_ARG));
+    let candidates = match error_file {
+        Some(pattern) => vec![working_dir.join(expand_pid(pattern, pid))],
+        None => {
+            let file_name = format!("hs_err_pid{}.log", pid);
+            vec![working_dir.join(&file_name), temp_dir().join(&file_name)]
+        }
+    };
+    let path = candidates
+        .into_iter()
+        .find(|path| path.is_file() && is_modified_since(path, start_time))?;
+    let file = File::open(&path).ok()?;
+    let lines = BufReader::new(file)
+        .lines()
+        .take(MAX_HEADER_LINES)
+        .filter_map(Result::ok);
+    Some(parse_header(path, lines))
+}
+
+/// Checks if the file at `path` was modified at or after `time`. Modification times
+/// are compared in whole seconds, since file systems store them with limited precision.
+fn is_modified_since(path: &Path, time: SystemTime) -> bool {
+    let secs = |time: SystemTime| time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs());
+    let modified = path.metadata().and_then(|metadata| metadata.modified());
+    match modified {
+        Ok(modified) => secs(modified) >= secs(time),
+        Err(_) => false,
+    }
+}
+
+/// HotSpot writes fatal error logs to `/tmp`, regardless of `TMPDIR`
+#[cfg(not(target_os = "windows"))]
+fn temp_dir() -> PathBuf {
+    PathBuf::from("/tmp")
+}
+
+#[cfg(target_os = "windows")]
+fn temp_dir() -> PathBuf {
+    std::env::temp_dir()
+}
+
+/// Replaces `%p` in the `pattern` of an `-XX:ErrorFile` value with the `pid`
+/// and `%%` with `%`.
+fn expand_pid(pattern: &str, pid: u32) -> String {
+    let mut result = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('p') => result.push_str(&pid.to_string()),
+            Some('%') => result.push('%'),
+            Some(other) => {
+                result.push('%');
+                result.push(other);
+            }
+            None => result.push('%'),
+        }
+    }
+    result
+}
+
+/// Parses the comment block at the start of a fatal error log, e.g.:
+///
+/// ```text
+/// #
+/// # A fatal error has been detected by the Java Runtime Environment:
+/// #
+/// #  SIGSEGV (0xb) at pc=0x00007f3c5e58b9f5, pid=4242, tid=4243
+/// #
+/// # JRE version: OpenJDK Runtime Environment (17.0.2+8) (build 17.0.2+8-86)
+/// # Problematic frame:
+/// # C  [libc.so.6+0x18b9f5]
+/// ```
+fn parse_header(path: PathBuf, lines: impl Iterator<Item = String>) -> CrashLog {
+    let mut crash_log = CrashLog {
+        path,
+        ..CrashLog::default()
+    };
+    let mut in_frame_section = false;
+    let header = lines.take_while(|line| line.starts_with('#'));
+    for line in header {
+        let content = line.trim_start_matches('#').trim();
+        if content.is_empty() || content == FATAL_ERROR_HEADER {
+            continue;
+        }
+        if in_frame_section {
+            crash_log.problematic_frame = Some(content.to_string());
+            in_frame_section = false;
+        } else if content == PROBLEMATIC_FRAME_HEADER {
+            in_frame_section = true;
+        } else if let Some(version) = content.strip_prefix(JRE_VERSION_PREFIX) {
+            crash_log.java_version = Some(version.trim().to_string());
+        } else if crash_log.signal.is_none() {
+            // strip the program counter and process details
+            let signal = content.split(" at pc=").next().unwrap_or(content);
+            crash_log.signal = Some(signal.trim().to_string());
+        }
+    }
+    crash_log
+}
+
+#[cfg(test)]
+mod test {
+    use super::{expand_pid, find_crash_log, parse_header, CrashLog};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    const SEGV_LOG: &str = "#
+# A fatal error has been detected by the Java Runtime Environment:
+#
+#  SIGSEGV (0xb) at pc=0x00007f3c5e58b9f5, pid=4242, tid=4243
+#
+# JRE version: OpenJDK Runtime Environment (17.0.2+8) (build 17.0.2+8-86)
+# Java VM: OpenJDK 64-Bit Server VM (17.0.2+8-86, mixed mode, tiered, linux-amd64)
+# Problematic frame:
+# C  [libc.so.6+0x18b9f5]
+#
+# Core dump will be written. Default location: core.4242
+#
+
+---------------  S U M M A R Y ------------
+# not part of the header
+";
+
+    fn parse(content: &str) -> CrashLog {
+        parse_header(
+            PathBuf::from("hs_err.log"),
+            content.lines().map(str::to_string),
+        )
+    }
+
+    #[test]
+    fn test_parse_signal_header() {
+        let crash_log = parse(SEGV_LOG);
+        assert_eq!(crash_log.signal.unwrap(), "SIGSEGV (0xb)");
+        assert_eq!(
+            crash_log.problematic_frame.unwrap(),
+            "C  [libc.so.6+0x18b9f5]"
+        );
+        assert_eq!(
+            crash_log.java_version.unwrap(),
+            "OpenJDK Runtime Environment (17.0.2+8) (build 17.0.2+8-86)"
+        );
+    }
+
+    #[test]
+    fn test_parse_out_of_memory_header() {
+        let content = "#\n# There is insufficient memory for the Java Runtime Environment.\n\
+                       # Native memory allocation (mmap) failed to map 65536 bytes\n";
+        let crash_log = parse(content);
+        let expected = "There is insufficient memory for the Java Runtime Environment.";
+        assert_eq!(crash_log.signal.unwrap(), expected);
+        assert_eq!(crash_log.problematic_frame, None);
+    }
+
+    #[test]
+    fn test_expand_pid() {
+        assert_eq!(expand_pid("logs/crash_%p.log", 42), "logs/crash_42.log");
+        assert_eq!(expand_pid("100%%_%p%", 7), "100%_7%");
+    }
+
+    #[test]
+    fn test_find_crash_log_from_error_file_arg() {
+        let dir = std::env::temp_dir().join(format!("eclipse_crash_log_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("crash_4242.log"), SEGV_LOG).unwrap();
+        let args = [
+            "-Xmx1g",
+            "-XX:ErrorFile=crash_%p.log",
+            "-jar",
+            "launcher.jar",
+        ];
+
+        let start_time = SystemTime::now() - Duration::from_secs(10);
+        let crash_log = find_crash_log(&args, 4242, &dir, start_time).unwrap();
+        assert_eq!(crash_log.path, dir.join("crash_4242.log"));
+        assert_eq!(crash_log.signal.unwrap(), "SIGSEGV (0xb)");
+        assert_eq!(find_crash_log(&args, 4243, &dir, start_time), None);
+
+        // left over from an earlier process with the same pid
+        let later_start = SystemTime::now() + Duration::from_secs(10);
+        assert_eq!(find_crash_log(&args, 4242, &dir, later_start), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
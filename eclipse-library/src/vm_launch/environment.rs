@@ -0,0 +1,311 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides the type `Environment`, which computes the environment
+//! variables of the JVM from the options `--launcher.cleanEnv`, `--launcher.unsetEnv`
+//! and `--launcher.env`, applied in this order to the environment of the launcher.
+//!
+//! `--launcher.cleanEnv` removes all variables, except the ones in `DEFAULT_ALLOWED`
+//! and the names given by `--launcher.keepEnv`. Names ending with `*` allow all
+//! variables with this prefix. Values of `--launcher.env` support the ini file
+//! variables (see `ini_variables`), `${env:NAME}` is resolved against the environment
+//! built so far, so it sees the variables set by preceding `--launcher.env` options.
+//! For this reason the ini file reader keeps these values unexpanded, and they are read
+//! from the ini files along with their location by `env_vars_from_ini`.
+
+use crate::errors::EclipseLibErr;
+use eclipse_common::arg_parser::OptionValue;
+use eclipse_common::ini_reader::IniChain;
+use eclipse_common::ini_variables::{expand_variables_with_env, VariableContext};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Variables kept by `--launcher.cleanEnv`, which are needed to run a desktop application
+#[cfg(not(target_os = "windows"))]
+const DEFAULT_ALLOWED: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "TMPDIR",
+    "LANG",
+    "LC_*",
+    "DISPLAY",
+    "WAYLAND_DISPLAY",
+    "XAUTHORITY",
+    "XDG_*",
+    "DBUS_SESSION_BUS_ADDRESS",
+];
+
+/// Variables kept by `--launcher.cleanEnv`, which are needed to run a desktop application
+#[cfg(target_os = "windows")]
+const DEFAULT_ALLOWED: &[&str] = &[
+    "PATH",
+    "PATHEXT",
+    "SYSTEMROOT",
+    "SYSTEMDRIVE",
+    "WINDIR",
+    "COMSPEC",
+    "TEMP",
+    "TMP",
+    "USERNAME",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PROGRAMDATA",
+];
+
+/// A value of `--launcher.env`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EnvVar {
+    pub name: String,
+
+    /// The value before expanding variables
+    pub value: String,
+
+    /// Directory of the ini file the value is read from, `None` for values of the command line
+    pub ini_dir: Option<PathBuf>,
+}
+
+impl OptionValue for EnvVar {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value.split_once('=') {
+            Some((name, value)) if is_valid_name(name) => Ok(EnvVar {
+                name: name.to_string(),
+                value: value.to_string(),
+                ini_dir: None,
+            }),
+            _ => Err("expected <name>=<value>".to_string()),
+        }
+    }
+}
+
+/// Returns the `--launcher.env` values of the ini files in the `ini_chain`, ordered from
+/// lowest to highest precedence. Invalid values are skipped, the launcher reports them.
+pub fn env_vars_from_ini(ini_chain: &IniChain) -> Vec<EnvVar> {
+    let (env_values, _) = ini_chain.split_env_values();
+    env_values
+        .into_iter()
+        .filter_map(|setting| {
+            let var = EnvVar::parse_value(setting.value).ok()?;
+            Some(EnvVar {
+                ini_dir: setting.path.parent().map(Path::to_path_buf),
+                ..var
+            })
+        })
+        .collect()
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains('=') && !name.contains('\0')
+}
+
+/// The environment of the JVM, see module documentation
+#[derive(Debug, Default)]
+pub struct Environment {
+    /// The variables of the JVM, `None` if the JVM inherits the launcher's environment
+    vars: Option<BTreeMap<String, String>>,
+}
+
+impl Environment {
+    /// Computes the environment of the JVM from the launcher's environment and the option
+    /// values. If `clean_env` is `true`, only the essential and the `keep` variables are
+    /// inherited. Variables in the `set` values are expanded relative to the directory of
+    /// their ini file, values of the command line relative to the `launcher_dir`.
+    /// Variables of the launcher environment which are not valid unicode are dropped, if
+    /// the environment is modified.
+    pub fn resolve(
+        clean_env: bool,
+        keep: &[String],
+        unset: &[String],
+        set: &[EnvVar],
+        launcher_dir: &Path,
+    ) -> Result<Environment, EclipseLibErr> {
+        let allowed: Option<Vec<&str>> = if clean_env {
+            Some(keep.iter().map(String::as_str).collect())
+        } else {
+            None
+        };
+        if allowed.is_none() && unset.is_empty() && set.is_empty() {
+            return Ok(Environment::default());
+        }
+        // std::env::vars panics on variables which are not valid unicode
+        let current = std::env::vars_os().filter_map(|(name, value)| {
+            Some((name.into_string().ok()?, value.into_string().ok()?))
+        });
+        let vars = compute(allowed.as_deref(), unset, set, current, launcher_dir)?;
+        Ok(Environment { vars: Some(vars) })
+    }
+
+    /// Sets the environment of the `command` starting the JVM
+    pub fn add_to(&self, command: &mut Command) {
+        if let Some(vars) = &self.vars {
+            command.env_clear().envs(vars);
+        }
+    }
+
+    /// Sets the environment of this process, used if the JVM is started in-process via JNI
+    pub fn apply(&self) {
+        let vars = match &self.vars {
+            Some(vars) => vars,
+            None => return,
+        };
+        for (name, _) in std::env::vars_os() {
+            let keep = name.to_str().is_some_and(|name| vars.contains_key(name));
+            if !keep {
+                std::env::remove_var(name);
+            }
+        }
+        for (name, value) in vars {
+            std::env::set_var(name, value);
+        }
+    }
+}
+
+/// Computes the environment of the JVM from the `current` environment, by keeping only the
+/// `allowed` variables (if given), removing the `unset` ones and adding the `set` ones.
+fn compute(
+    allowed: Option<&[&str]>,
+    unset: &[String],
+    set: &[EnvVar],
+    current: impl Iterator<Item = (String, String)>,
+    launcher_dir: &Path,
+) -> Result<BTreeMap<String, String>, EclipseLibErr> {
+    let mut env: BTreeMap<String, String> = match allowed {
+        Some(allowed) => current
+            .filter(|(name, _)| is_allowed(name, allowed))
+            .collect(),
+        None => current.collect(),
+    };
+    for name in unset {
+        env.retain(|existing, _| !names_equal(existing, name));
+    }
+    for var in set {
+        // values of the command line are treated like values of the launcher's ini file
+        let context = VariableContext {
+            launcher_dir,
+            ini_dir: var.ini_dir.as_deref().unwrap_or(launcher_dir),
+        };
+        let lookup = |name: &str| {
+            env.iter()
+                .find(|(existing, _)| names_equal(existing, name))
+                .map(|(_, value)| value.clone())
+        };
+        let value = expand_variables_with_env(&var.value, &context, &lookup)
+            .map_err(|err| EclipseLibErr::EnvVariableFail(var.name.clone(), err))?;
+        env.retain(|existing, _| !names_equal(existing, &var.name));
+        env.insert(var.name.clone(), value);
+    }
+    Ok(env)
+}
+
+/// Returns whether the variable `name` is in the `allowed` list or `DEFAULT_ALLOWED`
+fn is_allowed(name: &str, allowed: &[&str]) -> bool {
+    DEFAULT_ALLOWED
+        .iter()
+        .chain(allowed)
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name
+                .get(..prefix.len())
+                .is_some_and(|name_start| names_equal(name_start, prefix)),
+            None => names_equal(name, pattern),
+        })
+}
+
+/// Environment variable names are case insensitive on Windows
+#[cfg(target_os = "windows")]
+fn names_equal(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn names_equal(a: &str, b: &str) -> bool {
+    a == b
+}
+
+#[cfg(test)]
+mod test {
+    use super::{compute, EnvVar};
+    use eclipse_common::arg_parser::OptionValue;
+    use std::path::{Path, PathBuf};
+
+    fn current() -> impl Iterator<Item = (String, String)> {
+        let vars = vec![
+            ("PATH", "/usr/bin"),
+            ("LC_ALL", "C"),
+            ("MAVEN_OPTS", "-Xmx1g"),
+            ("SECRET_TOKEN", "42"),
+        ];
+        vars.into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+    }
+
+    fn env_var(value: &str) -> EnvVar {
+        EnvVar::parse_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_parse_env_var() {
+        let var = env_var("GDK_SCALE=2");
+        assert_eq!(var.name, "GDK_SCALE");
+        assert_eq!(var.value, "2");
+        assert_eq!(env_var("EMPTY=").value, "");
+        assert!(EnvVar::parse_value("GDK_SCALE").is_err());
+        assert!(EnvVar::parse_value("=2").is_err());
+    }
+
+    #[test]
+    fn test_set_and_unset() {
+        let set = [env_var("GDK_SCALE=2"), env_var("PATH=/opt/bin:${env:PATH}")];
+        let unset = ["MAVEN_OPTS".to_string()];
+        let env = compute(None, &unset, &set, current(), Path::new("/opt/eclipse")).unwrap();
+        assert_eq!(env["GDK_SCALE"], "2");
+        assert_eq!(env["PATH"], "/opt/bin:/usr/bin");
+        assert!(!env.contains_key("MAVEN_OPTS"));
+        assert!(env.contains_key("SECRET_TOKEN"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_clean_env_keeps_allowed() {
+        let set = [env_var("SWT_GTK3=${env:MISSING:-0}")];
+        let allowed = ["MAVEN_OPTS"];
+        let env = compute(Some(&allowed), &[], &set, current(), Path::new("/")).unwrap();
+        let names: Vec<&str> = env.keys().map(String::as_str).collect();
+        assert_eq!(names, ["LC_ALL", "MAVEN_OPTS", "PATH", "SWT_GTK3"]);
+        assert_eq!(env["SWT_GTK3"], "0");
+    }
+
+    #[test]
+    fn test_unresolved_variable() {
+        let set = [env_var("FOO=${env:ECLIPSE_UNSET_TEST_VAR}")];
+        assert!(compute(None, &[], &set, current(), Path::new("/")).is_err());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_ini_dir_of_value() {
+        let from_ini = EnvVar {
+            ini_dir: Some(PathBuf::from("/etc/eclipse")),
+            ..env_var("CONF=${ini.dir}/conf")
+        };
+        let from_command_line = env_var("LOG=${ini.dir}/log");
+        let set = [from_ini, from_command_line];
+        let env = compute(None, &[], &set, current(), Path::new("/opt/eclipse")).unwrap();
+        assert_eq!(env["CONF"], "/etc/eclipse/conf");
+        assert_eq!(env["LOG"], "/opt/eclipse/log");
+    }
+}
@@ -16,26 +16,49 @@ use crate::errors::{EclipseLibErr, VmRunErr, VmStartErr};
 use crate::shared_mem::SharedMem;
 use std::ffi::OsString;
 use std::process::Command;
-use std::time::{Duration, Instant};
-use super::{ArgList, StopAction, os, RESTART_LAST_EC, RESTART_NEW_EC, };
+use std::time::{Duration, Instant, SystemTime};
+use super::{ArgList, RestartReason, StopAction, os, RESTART_LAST_EC, RESTART_NEW_EC, };
+use super::crash_log::find_crash_log;
+use super::environment::Environment;
+use super::resource_limits::ResourceLimits;
+use super::startup_watchdog::{StartupWatchdog, WatchdogAction};
 
 /// Launches the VM found under `exe_path`, with the arguments provided
 /// by `all_args`. When finished, the method will interpret the return
 /// code, and may read from the shared memory that access is provided 
 /// to via `shared_mem`. If needed, this method will adjust `exe_path` and
 /// `all_args` for a subsequent launch. The return value will give the
-/// caller instructions on how to proceed.
+/// caller instructions on how to proceed. If a `startup_watchdog` is given,
+/// it observes the startup of the VM and may terminate it. The `resource_limits`
+/// are applied to the VM process before it executes, which runs in the `environment`.
 pub(super) fn launch_exe<S: SharedMem>(
     exe_path: &mut OsString,
     all_args: &mut ArgList,
     shared_mem: &S,
+    mut startup_watchdog: Option<&mut StartupWatchdog>,
+    resource_limits: &ResourceLimits,
+    environment: &Environment,
 ) -> Result<StopAction, EclipseLibErr> {
     use VmStartErr::*;
     let mut command = Command::new(&exe_path);
-    let mut child = all_args
-        .add_to(&mut command)
-        .spawn()
-        .map_err(ExeStartErr)?;
+    all_args.add_to(&mut command);
+    let limit_failures = resource_limits.add_to(&mut command);
+    // before preparing the watchdog, which adds an environment variable
+    environment.add_to(&mut command);
+    if let Some(watchdog) = startup_watchdog.as_mut() {
+        watchdog.prepare(&mut command);
+    }
+    let start_time = SystemTime::now();
+    let (spawned, limit_warnings) = limit_failures.check(command.spawn());
+    for warning in limit_warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    let mut child = spawned.map_err(ExeStartErr)?;
+    let pid = child.id();
+    if let Some(watchdog) = startup_watchdog.as_mut() {
+        watchdog.attach(&mut child);
+    }
+    let mut startup_timeout = None;
 
     let mut last_check = Instant::now();
     let timeout = Duration::from_millis(100);
@@ -48,6 +71,16 @@ pub(super) fn launch_exe<S: SharedMem>(
             false
         } else {
             last_check = Instant::now();
+            if let Some(watchdog) = startup_watchdog.as_mut() {
+                if let WatchdogAction::Terminate(thread_dump) = watchdog.check(pid) {
+                    startup_timeout = Some(VmRunErr::StartupTimeout {
+                        timeout: watchdog.timeout(),
+                        thread_dump,
+                    });
+                    // may fail if already terminated, we don't care
+                    let _ = child.kill();
+                }
+            }
             match child.try_wait() {
                 Ok(None) => false,
                 _ => true,
@@ -55,15 +88,26 @@ pub(super) fn launch_exe<S: SharedMem>(
         }
     };
     os::program_loop(is_terminated_callback);
+    if let Some(err) = startup_timeout {
+        // reap the terminated process
+        let _ = child.wait();
+        Err(err)?
+    }
 
     // Check why we terminated
     match child.try_wait() {
         // Regular termination
         Ok(Some(exit_status)) => match exit_status.code() {
-            Some(return_code) => {
-                result_from_exe_exit_code(exe_path, all_args, shared_mem, return_code)
-            }
-            None => Err(VmRunErr::UnknownErr)?,
+            Some(return_code) => result_from_exe_exit_code(
+                exe_path,
+                all_args,
+                shared_mem,
+                return_code,
+                pid,
+                start_time,
+            ),
+            // Terminated by a signal, e.g. SIGABRT after writing a fatal error log
+            None => Err(abnormal_exit_err(all_args, pid, start_time, None))?,
         },
         // Not terminated?
         #[allow(unused_must_use)] // may fail if already terminated, we don't care
@@ -87,10 +131,16 @@ fn result_from_exe_exit_code(
     arg_list: &mut ArgList<'_>,
     shared_mem: &impl SharedMem,
     code: i32,
+    pid: u32,
+    start_time: SystemTime,
 ) -> Result<StopAction, EclipseLibErr> {
     match code {
         0 => Ok(StopAction::Nothing),
-        RESTART_LAST_EC => Ok(StopAction::RestartVM),
+        RESTART_LAST_EC => {
+            // exit data is optional here, it is only used to report restart reasons
+            let exit_data = shared_mem.read().unwrap_or_default();
+            Ok(StopAction::RestartVM(RestartReason { exit_code: code, exit_data }))
+        }
         RESTART_NEW_EC => {
             // Update list of commands from shared memory
             let shared_str = shared_mem.read()?;
@@ -101,8 +151,30 @@ fn result_from_exe_exit_code(
             let new_args: Vec<String> = str_iter.map(String::from).collect();
             *arg_list = new_args.into();
 
-            Ok(StopAction::RestartVM)
+            Ok(StopAction::RestartVM(RestartReason {
+                exit_code: code,
+                exit_data: shared_str,
+            }))
         }
-        failure_code => Err(VmRunErr::FailureReturnCode(failure_code))?,
+        failure_code => Err(abnormal_exit_err(arg_list, pid, start_time, Some(failure_code)))?,
+    }
+}
+
+/// Returns the error for the JVM process `pid` started at `start_time`, terminated with
+/// `exit_code` (`None` if terminated by a signal). If the JVM crashed, the error holds
+/// a summary of the fatal error log written by the JVM.
+fn abnormal_exit_err(
+    all_args: &ArgList,
+    pid: u32,
+    start_time: SystemTime,
+    exit_code: Option<i32>,
+) -> VmRunErr {
+    // the JVM is started in the working directory of the launcher
+    let working_dir = std::env::current_dir().unwrap_or_default();
+    let crash_log = find_crash_log(&all_args.to_strs(), pid, &working_dir, start_time);
+    match (crash_log, exit_code) {
+        (Some(crash_log), _) => VmRunErr::Crashed { exit_code, crash_log },
+        (None, Some(code)) => VmRunErr::FailureReturnCode(code),
+        (None, None) => VmRunErr::UnknownErr,
     }
 }
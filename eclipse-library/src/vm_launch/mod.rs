@@ -19,20 +19,33 @@
 #[cfg_attr(target_os = "linux", path = "linux.rs")]
 #[cfg_attr(target_os = "windows", path = "windows.rs")]
 mod os;
+mod crash_log;
+mod environment;
 mod exe_launch;
 mod jni_launch;
+mod resource_limits;
+mod scheduling;
+mod startup_watchdog;
 mod common;
 
 use crate::errors::{EclipseLibErr, VmLaunchErr, VmRunErr, VmStartErr};
 use crate::shared_mem::SharedMem;
 use crate::vm_command::VmArgs;
 use crate::vm_lookup::JvmLaunchMode;
+use eclipse_common::eclipse_params_flags::{JAR, VMARGS};
 use std::borrow::Cow;
 use std::ffi::OsString;
+use std::ops::Range;
 use std::path::Path;
 use std::process::Command;
 use std::time::{Duration, Instant};
 
+pub use crash_log::CrashLog;
+pub use environment::{env_vars_from_ini, EnvVar, Environment};
+pub use resource_limits::{ResourceLimit, ResourceLimits};
+pub use scheduling::{CpuAffinity, IoPriority, Niceness, Scheduling};
+pub use startup_watchdog::{mark_startup_complete, StartupWatchdog};
+
 const RESTART_LAST_EC: i32 = 23;
 const RESTART_NEW_EC: i32 = 24;
 
@@ -63,6 +76,64 @@ impl<'a> ArgList<'a> {
             ArgList::Borrowed(vec) => cmd.args(vec.iter()),
         }
     }
+
+    /// Returns the argument strings as slices
+    fn to_strs(&self) -> Vec<&str> {
+        match self {
+            ArgList::Owned(vec) => vec.iter().map(String::as_str).collect(),
+            ArgList::Borrowed(vec) => vec.clone(),
+        }
+    }
+
+    /// Replaces all VM arguments starting with `prefix` by `arg`. If there is no such
+    /// argument, `arg` is added as first VM argument and as first argument after
+    /// `-vmargs`, which is used to relaunch the VM via exit data. Program arguments
+    /// are left unchanged, see `vm_arg_ranges`.
+    fn replace_vm_arg(&mut self, prefix: &str, arg: &str) {
+        let mut args: Vec<String> = self.to_strs().into_iter().map(String::from).collect();
+        let ranges = vm_arg_ranges(&args);
+        let mut replaced = false;
+        for range in &ranges {
+            for existing in args[range.clone()].iter_mut().filter(|s| s.starts_with(prefix)) {
+                *existing = arg.to_string();
+                replaced = true;
+            }
+        }
+        if !replaced {
+            // insert from the back, so the start of the preceding ranges stays valid
+            for range in ranges.iter().rev() {
+                args.insert(range.start, arg.to_string());
+            }
+        }
+        *self = ArgList::Owned(args);
+    }
+}
+
+/// Returns the index ranges of the VM arguments in `args`: the arguments in front of
+/// `-jar`, and the arguments following `-vmargs` up to the next `-jar`. Arguments
+/// following `-jar <jar file>` are passed to the Java application, not to the JVM.
+fn vm_arg_ranges<S: AsRef<str>>(args: &[S]) -> Vec<Range<usize>> {
+    let jar_from = |start: usize| {
+        args[start..]
+            .iter()
+            .position(|arg| arg.as_ref() == JAR)
+            .map_or(args.len(), |pos| start + pos)
+    };
+    let relaunch_range = args
+        .iter()
+        .position(|arg| arg.as_ref() == VMARGS)
+        .map(|vmargs_pos| vmargs_pos + 1..jar_from(vmargs_pos + 1));
+    std::iter::once(0..jar_from(0))
+        .chain(relaunch_range)
+        .collect()
+}
+
+/// Why the JVM requested to be restarted: its exit code and
+/// the exit data it wrote to the shared memory (may be empty).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RestartReason {
+    pub exit_code: i32,
+    pub exit_data: String,
 }
 
 /// Based on return code and shared data written by the started JVM,
@@ -70,7 +141,7 @@ impl<'a> ArgList<'a> {
 #[derive(Debug)]
 pub enum StopAction {
     /// Restart VM via the JavaLauncher
-    RestartVM,
+    RestartVM(RestartReason),
 
     /// Restart the launcher executable with the same arguments
     RestartExeLastArgs,
@@ -90,6 +161,9 @@ pub enum JavaLauncher<'a, S: SharedMem> {
         exe_path: OsString,
         all_args: ArgList<'a>,
         shared_mem: &'a S,
+        startup_watchdog: Option<Box<StartupWatchdog>>,
+        resource_limits: ResourceLimits,
+        environment: Environment,
     },
     JniLaunch {
         jni_lib: &'a Path,
@@ -119,7 +193,77 @@ impl<'a, S: SharedMem> JavaLauncher<'a, S> {
                 exe_path,
                 all_args,
                 shared_mem,
-            } => exe_launch::launch_exe(exe_path, all_args, *shared_mem),
+                startup_watchdog,
+                resource_limits,
+                environment,
+            } => exe_launch::launch_exe(
+                exe_path,
+                all_args,
+                *shared_mem,
+                startup_watchdog.as_deref_mut(),
+                resource_limits,
+                environment,
+            ),
+        }
+    }
+
+    /// Returns the last VM argument starting with `prefix` (e.g. `-Xmx`).
+    /// Always returns `None` when launching via JNI.
+    pub fn vm_arg(&self, prefix: &str) -> Option<&str> {
+        match self {
+            JavaLauncher::ExeLaunch { all_args, .. } => {
+                let args = all_args.to_strs();
+                let first_range = vm_arg_ranges(&args).swap_remove(0);
+                args[first_range]
+                    .iter()
+                    .rev()
+                    .find(|arg| arg.starts_with(prefix))
+                    .copied()
+            }
+            JavaLauncher::JniLaunch { .. } => None,
+        }
+    }
+
+    /// Replaces the VM arguments starting with `prefix` by `arg` for subsequent launches,
+    /// or adds `arg` if there is no such argument. Has no effect when launching via JNI.
+    pub fn replace_vm_arg(&mut self, prefix: &str, arg: &str) {
+        if let JavaLauncher::ExeLaunch { all_args, .. } = self {
+            all_args.replace_vm_arg(prefix, arg);
+        }
+    }
+
+    /// Sets the watchdog observing the startup of the JVM on every launch.
+    /// Has no effect when launching via JNI, since the JVM runs in this process.
+    pub fn set_startup_watchdog(&mut self, watchdog: StartupWatchdog) {
+        if let JavaLauncher::ExeLaunch {
+            startup_watchdog, ..
+        } = self
+        {
+            startup_watchdog.replace(Box::new(watchdog));
+        }
+    }
+
+    /// Sets the resource limits of the JVM. When launching via JNI, the limits are
+    /// applied to this process immediately, since the JVM is created in-process.
+    /// Returns warnings for limits that could not be applied.
+    pub fn set_resource_limits(&mut self, limits: ResourceLimits) -> Vec<String> {
+        match self {
+            JavaLauncher::ExeLaunch {
+                resource_limits, ..
+            } => {
+                *resource_limits = limits;
+                Vec::new()
+            }
+            JavaLauncher::JniLaunch { .. } => limits.apply(),
+        }
+    }
+
+    /// Sets the environment of the JVM. When launching via JNI, the environment
+    /// of this process is modified immediately, since the JVM is created in-process.
+    pub fn set_environment(&mut self, env: Environment) {
+        match self {
+            JavaLauncher::ExeLaunch { environment, .. } => *environment = env,
+            JavaLauncher::JniLaunch { .. } => env.apply(),
         }
     }
 
@@ -137,6 +281,9 @@ impl<'a, S: SharedMem> JavaLauncher<'a, S> {
                 exe_path: exe.into(),
                 all_args: concat_args(vm_args).into(),
                 shared_mem: shared_mem_param,
+                startup_watchdog: None,
+                resource_limits: ResourceLimits::default(),
+                environment: Environment::default(),
             },
             LaunchJni {
                 jni_lib: ref lib_path,
@@ -167,3 +314,33 @@ fn to_str_iter<'e>(cows: &'e [Cow<'e, str>]) -> impl Iterator<Item = &'e str> {
 }
 
 // Actual launching
+
+#[cfg(test)]
+mod test {
+    use super::ArgList;
+
+    #[test]
+    fn test_replace_vm_arg_existing() {
+        let mut args: ArgList = vec!["-Xmx1g", "-jar", "l.jar", "-vmargs", "-Xmx1g"].into();
+        args.replace_vm_arg("-Xmx", "-Xmx2g");
+        assert_eq!(args.to_strs(), ["-Xmx2g", "-jar", "l.jar", "-vmargs", "-Xmx2g"]);
+    }
+
+    #[test]
+    fn test_replace_vm_arg_added() {
+        let mut args: ArgList = vec!["-jar", "l.jar", "-vmargs", "-jar", "l.jar"].into();
+        args.replace_vm_arg("-Xmx", "-Xmx2g");
+        let expected = ["-Xmx2g", "-jar", "l.jar", "-vmargs", "-Xmx2g", "-jar", "l.jar"];
+        assert_eq!(args.to_strs(), expected);
+    }
+
+    #[test]
+    fn test_replace_vm_arg_keeps_program_args() {
+        let args = vec!["-jar", "l.jar", "-Xmx1g", "-vmargs", "-jar", "l.jar", "-Xmx1g"];
+        let mut args: ArgList = args.into();
+        args.replace_vm_arg("-Xmx", "-Xmx2g");
+        let expected = ["-Xmx2g", "-jar", "l.jar", "-Xmx1g", "-vmargs"];
+        assert_eq!(args.to_strs()[..5], expected);
+        assert_eq!(args.to_strs()[5..], ["-Xmx2g", "-jar", "l.jar", "-Xmx1g"]);
+    }
+}
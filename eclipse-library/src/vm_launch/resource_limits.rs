@@ -0,0 +1,583 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides the resource limits (`setrlimit`) of the JVM, configured via
+//! `--launcher.rlimit <resource>=<soft>[:<hard>]`. The soft limit may be `hard` to
+//! raise it to the hard limit, both limits may be `unlimited`. If `nofile` is not
+//! configured, its soft limit is raised to the hard limit, since large workspaces
+//! easily exceed the common default of 1024 open files.
+//!
+//! The limits are validated against the limits of the launcher process by
+//! `ResourceLimits::resolve`, invalid limits are reported as warnings. Limits of
+//! open files are capped to the maximum of the platform. If a valid limit cannot be
+//! set for the JVM nevertheless (e.g. in a restricted container), the JVM is started
+//! without it and a warning is reported. Only limits configured via `--launcher.rlimit`
+//! prevent the JVM from starting. Resource limits are not supported on Windows.
+
+use eclipse_common::arg_parser::OptionValue;
+use std::io;
+use std::process::{Child, Command};
+
+#[cfg(not(target_os = "windows"))]
+use std::os::unix::net::UnixStream;
+
+const UNLIMITED: &str = "unlimited";
+const HARD: &str = "hard";
+
+/// The resources supported by `--launcher.rlimit`, named like the `ulimit` options
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Resource {
+    NoFile,
+    NProc,
+    Core,
+    Stack,
+    AddressSpace,
+    Data,
+    FileSize,
+    MemLock,
+    Cpu,
+}
+
+const RESOURCE_NAMES: &[(&str, Resource)] = &[
+    ("nofile", Resource::NoFile),
+    ("nproc", Resource::NProc),
+    ("core", Resource::Core),
+    ("stack", Resource::Stack),
+    ("as", Resource::AddressSpace),
+    ("data", Resource::Data),
+    ("fsize", Resource::FileSize),
+    ("memlock", Resource::MemLock),
+    ("cpu", Resource::Cpu),
+];
+
+impl Resource {
+    fn name(self) -> &'static str {
+        RESOURCE_NAMES
+            .iter()
+            .find(|(_, resource)| *resource == self)
+            .map(|(name, _)| *name)
+            .unwrap_or_default()
+    }
+}
+
+/// A limit value given to `--launcher.rlimit`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LimitValue {
+    Value(u64),
+    Unlimited,
+
+    /// The hard limit, only valid as soft limit
+    Hard,
+}
+
+/// A value of `--launcher.rlimit`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ResourceLimit {
+    pub resource: Resource,
+    pub soft: LimitValue,
+
+    /// If `None`, the hard limit of the launcher process is kept
+    pub hard: Option<LimitValue>,
+}
+
+impl OptionValue for ResourceLimit {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        let (name, limits) = value
+            .split_once('=')
+            .ok_or_else(|| "expected <resource>=<soft>[:<hard>]".to_string())?;
+        let resource = RESOURCE_NAMES
+            .iter()
+            .find(|(resource_name, _)| *resource_name == name)
+            .map(|(_, resource)| *resource)
+            .ok_or_else(|| {
+                let names: Vec<&str> = RESOURCE_NAMES.iter().map(|(name, _)| *name).collect();
+                format!(
+                    "unknown resource '{}', expected one of {}",
+                    name,
+                    names.join(", ")
+                )
+            })?;
+        let (soft, hard) = match limits.split_once(':') {
+            Some((soft, hard)) => (soft, Some(hard)),
+            None => (limits, None),
+        };
+        let hard = match hard.map(parse_limit_value).transpose()? {
+            Some(LimitValue::Hard) => return Err("the hard limit cannot be 'hard'".to_string()),
+            hard => hard,
+        };
+        Ok(ResourceLimit {
+            resource,
+            soft: parse_limit_value(soft)?,
+            hard,
+        })
+    }
+}
+
+fn parse_limit_value(value: &str) -> Result<LimitValue, String> {
+    match value {
+        UNLIMITED => Ok(LimitValue::Unlimited),
+        HARD => Ok(LimitValue::Hard),
+        _ => value
+            .parse()
+            .map(LimitValue::Value)
+            .map_err(|_| format!("'{}' is not a number, '{}' or '{}'", value, UNLIMITED, HARD)),
+    }
+}
+
+/// The limits derived from the `--launcher.rlimit` values, which are applied to the JVM
+#[derive(Debug, Default)]
+pub struct ResourceLimits {
+    #[cfg(not(target_os = "windows"))]
+    limits: Vec<JvmLimit>,
+}
+
+/// A limit to apply to the JVM. Limits not `configured` by the user are defaults,
+/// which are skipped if they cannot be set.
+#[cfg(not(target_os = "windows"))]
+#[derive(Debug, Clone, Copy)]
+struct JvmLimit {
+    resource: Resource,
+    limit: Limit,
+    configured: bool,
+}
+
+/// Receives the limits the process started by a `Command` failed to set,
+/// see `ResourceLimits::add_to`
+#[derive(Debug, Default)]
+pub struct LimitFailures {
+    #[cfg(not(target_os = "windows"))]
+    receiver: Option<(UnixStream, Vec<JvmLimit>)>,
+}
+
+/// Soft and hard limit of a resource, `libc::rlimit` does not implement `Debug` and `Eq`
+#[cfg(not(target_os = "windows"))]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct Limit {
+    soft: libc::rlim_t,
+    hard: libc::rlim_t,
+}
+
+#[cfg(not(target_os = "windows"))]
+impl Limit {
+    fn to_rlimit(self) -> libc::rlimit {
+        libc::rlimit {
+            rlim_cur: self.soft,
+            rlim_max: self.hard,
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+impl ResourceLimits {
+    /// Computes the limits to apply from the given `settings` and the current limits of
+    /// this process. Settings that cannot be applied are skipped and reported by the
+    /// returned warnings.
+    pub fn resolve(settings: &[ResourceLimit]) -> (ResourceLimits, Vec<String>) {
+        let nofile_configured = settings.iter().any(|s| s.resource == Resource::NoFile);
+        let default_nofile = ResourceLimit {
+            resource: Resource::NoFile,
+            soft: LimitValue::Hard,
+            hard: None,
+        };
+        let settings = settings.iter().map(|setting| (*setting, true));
+        let defaults = Some((default_nofile, false)).filter(|_| !nofile_configured);
+        // only root may raise hard limits
+        let is_root = unsafe { libc::geteuid() } == 0;
+        let mut limits = Vec::new();
+        let mut warnings = Vec::new();
+        for (setting, configured) in settings.chain(defaults) {
+            let current = match get_limit(setting.resource) {
+                Some(current) => current,
+                None => {
+                    let name = setting.resource.name();
+                    warnings.push(format!("Resource limit {} cannot be read", name));
+                    continue;
+                }
+            };
+            match resolve_limit(&setting, current, is_root) {
+                Ok(limit) if limit == current => {}
+                Ok(limit) => limits.push(JvmLimit {
+                    resource: setting.resource,
+                    limit,
+                    configured,
+                }),
+                Err(msg) => warnings.push(msg),
+            }
+        }
+        (ResourceLimits { limits }, warnings)
+    }
+
+    /// Applies the limits to this process, used if the JVM is started in-process.
+    /// Returns warnings for the limits that could not be set.
+    pub fn apply(&self) -> Vec<String> {
+        self.limits
+            .iter()
+            .filter(|jvm_limit| !set_limit(jvm_limit.resource, jvm_limit.limit))
+            .map(|jvm_limit| {
+                let err = io::Error::last_os_error();
+                let name = jvm_limit.resource.name();
+                format!("Resource limit {} cannot be set: {}", name, err)
+            })
+            .collect()
+    }
+
+    /// Makes the process started by `command` apply the limits before executing the JVM.
+    /// The limits are validated by `resolve`. If one cannot be set nevertheless, the child
+    /// process reports it via the returned `LimitFailures` and continues with the next limit.
+    /// Only if a limit configured by the user fails, the JVM is not executed and spawning
+    /// the `command` fails.
+    pub fn add_to(&self, command: &mut Command) -> LimitFailures {
+        use std::os::unix::io::AsRawFd;
+        use std::os::unix::process::CommandExt;
+        if self.limits.is_empty() {
+            return LimitFailures::default();
+        }
+        let (reader, writer) = match UnixStream::pair() {
+            Ok(pair) => pair,
+            Err(err) => {
+                eprintln!("Warning: resource limits are not applied: {}", err);
+                return LimitFailures::default();
+            }
+        };
+        let limits = self.limits.clone();
+        // safe, setrlimit, write and reading errno are async-signal-safe and limits are
+        // not modified in the closure. The writer is closed when the JVM is executed.
+        unsafe {
+            command.pre_exec(move || {
+                for (index, jvm_limit) in limits.iter().enumerate() {
+                    if set_limit(jvm_limit.resource, jvm_limit.limit) {
+                        continue;
+                    }
+                    let err = io::Error::last_os_error();
+                    let errno = err.raw_os_error().unwrap_or_default();
+                    let mut failure = [0u8; FAILURE_LEN];
+                    failure[0] = index as u8;
+                    failure[1..].copy_from_slice(&errno.to_ne_bytes());
+                    let buf = failure.as_ptr() as *const libc::c_void;
+                    libc::write(writer.as_raw_fd(), buf, FAILURE_LEN);
+                    if jvm_limit.configured {
+                        return Err(err);
+                    }
+                }
+                Ok(())
+            });
+        }
+        LimitFailures {
+            receiver: Some((reader, self.limits.clone())),
+        }
+    }
+}
+
+/// Length of a failure sent by the child process: index of the limit and errno
+#[cfg(not(target_os = "windows"))]
+const FAILURE_LEN: usize = 1 + std::mem::size_of::<i32>();
+
+#[cfg(not(target_os = "windows"))]
+impl LimitFailures {
+    /// Checks the limits the child process could not set, has to be called with the result
+    /// of spawning the `Command`. Returns the result, where an error caused by a configured
+    /// limit names the limit, and warnings for the skipped limits.
+    pub fn check(self, spawned: io::Result<Child>) -> (io::Result<Child>, Vec<String>) {
+        use std::io::Read;
+        let (mut reader, limits) = match self.receiver {
+            Some(receiver) => receiver,
+            None => return (spawned, Vec::new()),
+        };
+        // the child wrote all failures before executing the JVM or failing, which
+        // happened before spawning returned. The writer is still held by the command.
+        let mut failures = Vec::new();
+        if reader.set_nonblocking(true).is_ok() {
+            let _ = reader.read_to_end(&mut failures);
+        }
+        let mut spawned = spawned;
+        let mut warnings = Vec::new();
+        for failure in failures.chunks_exact(FAILURE_LEN) {
+            let jvm_limit = match limits.get(failure[0] as usize) {
+                Some(jvm_limit) => jvm_limit,
+                None => continue,
+            };
+            let mut errno = [0u8; FAILURE_LEN - 1];
+            errno.copy_from_slice(&failure[1..]);
+            let err = io::Error::from_raw_os_error(i32::from_ne_bytes(errno));
+            let msg = format!(
+                "Resource limit {} cannot be set for the Java VM: {}",
+                jvm_limit.resource.name(),
+                err
+            );
+            match spawned {
+                Err(_) if jvm_limit.configured => spawned = Err(io::Error::new(err.kind(), msg)),
+                _ => warnings.push(msg),
+            }
+        }
+        (spawned, warnings)
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl ResourceLimits {
+    /// Resource limits are not supported on Windows, any `settings` are reported as warning.
+    pub fn resolve(settings: &[ResourceLimit]) -> (ResourceLimits, Vec<String>) {
+        let warnings = settings
+            .iter()
+            .map(|s| {
+                format!(
+                    "Resource limit {} is not supported on Windows",
+                    s.resource.name()
+                )
+            })
+            .collect();
+        (ResourceLimits::default(), warnings)
+    }
+
+    pub fn apply(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    pub fn add_to(&self, _command: &mut Command) -> LimitFailures {
+        LimitFailures::default()
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl LimitFailures {
+    pub fn check(self, spawned: io::Result<Child>) -> (io::Result<Child>, Vec<String>) {
+        (spawned, Vec::new())
+    }
+}
+
+/// Computes the limit to set for the `setting`, given the `current` limit.
+#[cfg(not(target_os = "windows"))]
+fn resolve_limit(setting: &ResourceLimit, current: Limit, is_root: bool) -> Result<Limit, String> {
+    let name = setting.resource.name();
+    let to_rlim = |value: LimitValue, hard: libc::rlim_t| match value {
+        LimitValue::Value(value) => value as libc::rlim_t,
+        LimitValue::Unlimited => libc::RLIM_INFINITY,
+        LimitValue::Hard => hard,
+    };
+    let hard = setting
+        .hard
+        .map_or(current.hard, |hard| to_rlim(hard, current.hard));
+    let soft = to_rlim(setting.soft, hard);
+    let Limit { soft, hard } = cap_limit(setting.resource, Limit { soft, hard });
+    if hard > current.hard && !is_root {
+        return Err(format!(
+            "Hard resource limit {} cannot be raised above {}",
+            name,
+            format_rlim(current.hard)
+        ));
+    }
+    if soft > hard {
+        return Err(format!(
+            "Soft resource limit {} ({}) exceeds the hard limit ({})",
+            name,
+            format_rlim(soft),
+            format_rlim(hard)
+        ));
+    }
+    Ok(Limit { soft, hard })
+}
+
+/// macOS rejects an unlimited soft limit of open files, it is capped to `OPEN_MAX`
+#[cfg(target_os = "macos")]
+fn cap_limit(resource: Resource, limit: Limit) -> Limit {
+    const OPEN_MAX: libc::rlim_t = 10240;
+    match resource {
+        Resource::NoFile => Limit {
+            soft: limit.soft.min(OPEN_MAX),
+            hard: limit.hard,
+        },
+        _ => limit,
+    }
+}
+
+/// Linux rejects limits of open files above `/proc/sys/fs/nr_open`, even for an unlimited
+/// hard limit. Both limits are capped to it.
+#[cfg(target_os = "linux")]
+fn cap_limit(resource: Resource, limit: Limit) -> Limit {
+    let nr_open = std::fs::read_to_string("/proc/sys/fs/nr_open")
+        .ok()
+        .and_then(|nr_open| nr_open.trim().parse::<libc::rlim_t>().ok());
+    match nr_open {
+        Some(nr_open) if resource == Resource::NoFile => Limit {
+            soft: limit.soft.min(nr_open),
+            hard: limit.hard.min(nr_open),
+        },
+        _ => limit,
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+fn cap_limit(_resource: Resource, limit: Limit) -> Limit {
+    limit
+}
+
+#[cfg(not(target_os = "windows"))]
+fn format_rlim(value: libc::rlim_t) -> String {
+    if value == libc::RLIM_INFINITY {
+        UNLIMITED.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn get_limit(resource: Resource) -> Option<Limit> {
+    let mut limit = Limit { soft: 0, hard: 0 }.to_rlimit();
+    // safe, limit is a valid rlimit struct to write to
+    if unsafe { libc::getrlimit(resource_id(resource), &mut limit) } != 0 {
+        return None;
+    }
+    Some(Limit {
+        soft: limit.rlim_cur,
+        hard: limit.rlim_max,
+    })
+}
+
+/// Sets the `limit` of the `resource` for this process, returns `false` on failure
+#[cfg(not(target_os = "windows"))]
+fn set_limit(resource: Resource, limit: Limit) -> bool {
+    // safe, the rlimit struct is valid and only read
+    unsafe { libc::setrlimit(resource_id(resource), &limit.to_rlimit()) == 0 }
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type ResourceId = libc::__rlimit_resource_t;
+
+#[cfg(all(
+    not(target_os = "windows"),
+    not(all(target_os = "linux", target_env = "gnu"))
+))]
+type ResourceId = libc::c_int;
+
+#[cfg(not(target_os = "windows"))]
+fn resource_id(resource: Resource) -> ResourceId {
+    match resource {
+        Resource::NoFile => libc::RLIMIT_NOFILE,
+        Resource::NProc => libc::RLIMIT_NPROC,
+        Resource::Core => libc::RLIMIT_CORE,
+        Resource::Stack => libc::RLIMIT_STACK,
+        Resource::AddressSpace => libc::RLIMIT_AS,
+        Resource::Data => libc::RLIMIT_DATA,
+        Resource::FileSize => libc::RLIMIT_FSIZE,
+        Resource::MemLock => libc::RLIMIT_MEMLOCK,
+        Resource::Cpu => libc::RLIMIT_CPU,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LimitValue, Resource, ResourceLimit};
+    use eclipse_common::arg_parser::OptionValue;
+
+    #[test]
+    fn test_parse_resource_limit() {
+        let limit = ResourceLimit::parse_value("nofile=hard").unwrap();
+        assert_eq!(limit.resource, Resource::NoFile);
+        assert_eq!(limit.soft, LimitValue::Hard);
+        assert_eq!(limit.hard, None);
+
+        let limit = ResourceLimit::parse_value("core=0:unlimited").unwrap();
+        assert_eq!(limit.soft, LimitValue::Value(0));
+        assert_eq!(limit.hard, Some(LimitValue::Unlimited));
+    }
+
+    #[test]
+    fn test_parse_invalid_resource_limit() {
+        assert!(ResourceLimit::parse_value("nofile").is_err());
+        assert!(ResourceLimit::parse_value("files=1024").is_err());
+        assert!(ResourceLimit::parse_value("nofile=many").is_err());
+        assert!(ResourceLimit::parse_value("nofile=1024:hard").is_err());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_resolve_limit() {
+        use super::{resolve_limit, Limit};
+        let current = Limit {
+            soft: 1024,
+            hard: 4096,
+        };
+        let raise = ResourceLimit::parse_value("nofile=hard").unwrap();
+        assert_eq!(resolve_limit(&raise, current, false).unwrap().soft, 4096);
+
+        let above_hard = ResourceLimit::parse_value("nofile=8192").unwrap();
+        assert!(resolve_limit(&above_hard, current, false).is_err());
+
+        let raise_hard = ResourceLimit::parse_value("nofile=8192:8192").unwrap();
+        assert!(resolve_limit(&raise_hard, current, false).is_err());
+        assert_eq!(
+            resolve_limit(&raise_hard, current, true).unwrap().hard,
+            8192
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_nofile_capped_to_nr_open() {
+        use super::{resolve_limit, Limit};
+        let nr_open: libc::rlim_t = match std::fs::read_to_string("/proc/sys/fs/nr_open") {
+            Ok(nr_open) => nr_open.trim().parse().unwrap(),
+            Err(_) => return,
+        };
+        let current = Limit {
+            soft: 1024,
+            hard: libc::RLIM_INFINITY,
+        };
+        let raise = ResourceLimit::parse_value("nofile=hard").unwrap();
+        let limit = resolve_limit(&raise, current, false).unwrap();
+        assert_eq!(
+            limit,
+            Limit {
+                soft: nr_open,
+                hard: nr_open
+            }
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_limit_failures_reported() {
+        use super::{JvmLimit, Limit, ResourceLimits};
+        use std::process::Command;
+        // exceeding nr_open fails even for root
+        let nr_open: libc::rlim_t = match std::fs::read_to_string("/proc/sys/fs/nr_open") {
+            Ok(nr_open) => nr_open.trim().parse().unwrap(),
+            Err(_) => return,
+        };
+        let too_large = Limit {
+            soft: nr_open + 1,
+            hard: nr_open + 1,
+        };
+        let limits = |configured| ResourceLimits {
+            limits: vec![JvmLimit {
+                resource: Resource::NoFile,
+                limit: too_large,
+                configured,
+            }],
+        };
+
+        let mut command = Command::new("true");
+        let failures = limits(false).add_to(&mut command);
+        let (spawned, warnings) = failures.check(command.spawn());
+        assert!(spawned.unwrap().wait().unwrap().success());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("nofile"));
+
+        let mut command = Command::new("true");
+        let failures = limits(true).add_to(&mut command);
+        let (spawned, warnings) = failures.check(command.spawn());
+        assert!(spawned.unwrap_err().to_string().contains("nofile"));
+        assert!(warnings.is_empty());
+    }
+}
@@ -0,0 +1,270 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides the type `Scheduling`, which applies the process scheduling
+//! options `--launcher.nice`, `--launcher.ioPriority` and `--launcher.cpuAffinity`.
+//!
+//! The settings are applied to the launcher process before the JVM is started.
+//! A JVM started as separate process inherits them, a JVM started via JNI runs
+//! in the launcher process anyway. The nice value is supported on all Unix
+//! platforms, I/O priority and CPU affinity only on Linux.
+
+use eclipse_common::arg_parser::{parse_choice, OptionValue};
+use std::fmt;
+
+/// Value of `--launcher.nice`, from -20 (highest priority) to 19 (lowest priority)
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Niceness(pub i32);
+
+const MIN_NICENESS: i32 = -20;
+const MAX_NICENESS: i32 = 19;
+
+impl OptionValue for Niceness {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value.parse() {
+            Ok(nice) if (MIN_NICENESS..=MAX_NICENESS).contains(&nice) => Ok(Niceness(nice)),
+            _ => Err(format!(
+                "expected an integer from {} to {}",
+                MIN_NICENESS, MAX_NICENESS
+            )),
+        }
+    }
+}
+
+/// I/O scheduling classes, see `ioprio_set(2)`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum IoClass {
+    RealTime,
+    BestEffort,
+    Idle,
+}
+
+const IO_CLASSES: &[(&str, IoClass)] = &[
+    ("realtime", IoClass::RealTime),
+    ("best-effort", IoClass::BestEffort),
+    ("idle", IoClass::Idle),
+];
+
+/// Priority level within the real-time and best-effort class, 0 is the highest
+const MAX_IO_LEVEL: u8 = 7;
+
+/// Priority level used if none is given, the kernel's default level
+const DEFAULT_IO_LEVEL: u8 = 4;
+
+/// Value of `--launcher.ioPriority`, `<class>[:<level>]`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct IoPriority {
+    pub class: IoClass,
+
+    /// Always 0 for `IoClass::Idle`, which has no levels
+    pub level: u8,
+}
+
+impl OptionValue for IoPriority {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        let (class, level) = match value.split_once(':') {
+            Some((class, level)) => (class, Some(level)),
+            None => (value, None),
+        };
+        let class = parse_choice(class, IO_CLASSES)?;
+        let level = match (class, level) {
+            (IoClass::Idle, Some(_)) => return Err("the idle class has no level".to_string()),
+            (IoClass::Idle, None) => 0,
+            (_, None) => DEFAULT_IO_LEVEL,
+            (_, Some(level)) => match level.parse() {
+                Ok(level) if level <= MAX_IO_LEVEL => level,
+                _ => return Err(format!("expected a level from 0 to {}", MAX_IO_LEVEL)),
+            },
+        };
+        Ok(IoPriority { class, level })
+    }
+}
+
+/// Formats the priority as given to `--launcher.ioPriority`
+impl fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let class = IO_CLASSES
+            .iter()
+            .find(|(_, class)| *class == self.class)
+            .map(|(name, _)| *name)
+            .unwrap_or_default();
+        match self.class {
+            IoClass::Idle => write!(f, "{}", class),
+            _ => write!(f, "{}:{}", class, self.level),
+        }
+    }
+}
+
+/// Highest CPU number accepted, the size of `cpu_set_t` on Linux
+const MAX_CPU: usize = 1023;
+
+/// Value of `--launcher.cpuAffinity`, a list of CPU numbers and ranges, e.g. `0-3,6`.
+/// Holds the CPU numbers in ascending order.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CpuAffinity(pub Vec<usize>);
+
+impl OptionValue for CpuAffinity {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        let invalid = || format!("expected CPU numbers from 0 to {}, e.g. 0-3,6", MAX_CPU);
+        let parse_cpu = |cpu: &str| match cpu.trim().parse() {
+            Ok(cpu) if cpu <= MAX_CPU => Ok(cpu),
+            _ => Err(invalid()),
+        };
+        let mut cpus = Vec::new();
+        for part in value.split(',') {
+            match part.split_once('-') {
+                Some((first, last)) => {
+                    let (first, last) = (parse_cpu(first)?, parse_cpu(last)?);
+                    if first > last {
+                        return Err(invalid());
+                    }
+                    cpus.extend(first..=last);
+                }
+                None => cpus.push(parse_cpu(part)?),
+            }
+        }
+        cpus.sort_unstable();
+        cpus.dedup();
+        Ok(CpuAffinity(cpus))
+    }
+}
+
+/// The scheduling settings to apply to the JVM, see module documentation
+#[derive(Debug, Default)]
+pub struct Scheduling {
+    pub nice: Option<Niceness>,
+    pub io_priority: Option<IoPriority>,
+    pub cpu_affinity: Option<CpuAffinity>,
+}
+
+impl Scheduling {
+    /// Applies the settings to this process. Returns a description of every applied
+    /// setting, or the reason it could not be applied.
+    pub fn apply(&self) -> Vec<Result<String, String>> {
+        let mut results = Vec::new();
+        if let Some(nice) = self.nice {
+            let result = set_niceness(nice).map(|_| format!("Set nice value {}", nice.0));
+            results.push(result.map_err(|err| format!("Cannot set nice value: {}", err)));
+        }
+        if let Some(io_priority) = self.io_priority {
+            let result =
+                set_io_priority(io_priority).map(|_| format!("Set I/O priority {}", io_priority));
+            results.push(result.map_err(|err| format!("Cannot set I/O priority: {}", err)));
+        }
+        if let Some(affinity) = &self.cpu_affinity {
+            let cpus: Vec<String> = affinity.0.iter().map(usize::to_string).collect();
+            let result =
+                set_cpu_affinity(affinity).map(|_| format!("Set CPU affinity {}", cpus.join(",")));
+            results.push(result.map_err(|err| format!("Cannot set CPU affinity: {}", err)));
+        }
+        results
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn set_niceness(nice: Niceness) -> Result<(), String> {
+    // safe, setpriority has no memory related preconditions. Who 0 is this process.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS as _, 0, nice.0) } != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn set_niceness(_nice: Niceness) -> Result<(), String> {
+    Err("not supported on Windows".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn set_io_priority(io_priority: IoPriority) -> Result<(), String> {
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+    let class = match io_priority.class {
+        IoClass::RealTime => 1,
+        IoClass::BestEffort => 2,
+        IoClass::Idle => 3,
+    };
+    let priority = (class << IOPRIO_CLASS_SHIFT) | libc::c_int::from(io_priority.level);
+    // safe, ioprio_set has no memory related parameters. Who 0 is this process.
+    let result = unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, priority) };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_io_priority(_io_priority: IoPriority) -> Result<(), String> {
+    Err("only supported on Linux".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn set_cpu_affinity(affinity: &CpuAffinity) -> Result<(), String> {
+    // safe, cpu_set_t is a plain bit set and CPU numbers are within its size
+    let result = unsafe {
+        let mut cpu_set: libc::cpu_set_t = std::mem::zeroed();
+        for cpu in &affinity.0 {
+            libc::CPU_SET(*cpu, &mut cpu_set);
+        }
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &cpu_set)
+    };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_cpu_affinity(_affinity: &CpuAffinity) -> Result<(), String> {
+    Err("only supported on Linux".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CpuAffinity, IoClass, IoPriority, Niceness};
+    use eclipse_common::arg_parser::OptionValue;
+
+    #[test]
+    fn test_parse_niceness() {
+        assert_eq!(Niceness::parse_value("10"), Ok(Niceness(10)));
+        assert_eq!(Niceness::parse_value("-20"), Ok(Niceness(-20)));
+        assert!(Niceness::parse_value("20").is_err());
+        assert!(Niceness::parse_value("low").is_err());
+    }
+
+    #[test]
+    fn test_parse_io_priority() {
+        let priority = IoPriority::parse_value("best-effort:7").unwrap();
+        assert_eq!(priority.class, IoClass::BestEffort);
+        assert_eq!(priority.level, 7);
+        assert_eq!(IoPriority::parse_value("realtime").unwrap().level, 4);
+        assert_eq!(
+            IoPriority::parse_value("idle").unwrap().class,
+            IoClass::Idle
+        );
+        assert_eq!(priority.to_string(), "best-effort:7");
+        assert!(IoPriority::parse_value("idle:3").is_err());
+        assert!(IoPriority::parse_value("best-effort:8").is_err());
+        assert!(IoPriority::parse_value("background").is_err());
+    }
+
+    #[test]
+    fn test_parse_cpu_affinity() {
+        let affinity = CpuAffinity::parse_value("4-6,0,5").unwrap();
+        assert_eq!(affinity, CpuAffinity(vec![0, 4, 5, 6]));
+        assert!(CpuAffinity::parse_value("3-1").is_err());
+        assert!(CpuAffinity::parse_value("0,,1").is_err());
+        assert!(CpuAffinity::parse_value("1024").is_err());
+    }
+}
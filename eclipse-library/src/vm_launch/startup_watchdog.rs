@@ -0,0 +1,312 @@
+/*******************************************************************************
+ * Copyright (c) 2019 Fraunhofer FOKUS and others.
+ *
+ * This program and the accompanying materials
+ * are made available under the terms of the Eclipse Public License 2.0
+ * which accompanies this distribution, and is available at
+ * https://www.eclipse.org/legal/epl-2.0/
+ *
+ * SPDX-License-Identifier: EPL-2.0
+ *
+ * Contributors:
+ *     Max Bureck (Fraunhofer FOKUS)
+ *******************************************************************************/
+
+//! This module provides the type `StartupWatchdog`, which implements
+//! `--launcher.startupTimeout` for JVMs started as separate process.
+//!
+//! The JVM signals a completed startup by calling `takedown_splash` or
+//! `set_launcher_info` through `JNIBridge`. Since these calls happen in the JVM
+//! process, they create a marker file, whose path is passed to the JVM via the
+//! environment variable `STARTUP_MARKER_ENV`. The marker is located in a `PrivateDir`,
+//! so other users cannot create it. If the marker file does not exist when the timeout
+//! elapsed, a thread dump is requested from the JVM via `SIGQUIT` (not available on
+//! Windows) and written to the launcher log, a new file in the temp folder. Afterwards
+//! the user is asked whether to keep waiting or to terminate the JVM.
+
+use crate::private_dir::PrivateDir;
+use eclipse_common::messagebox::ask_question;
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Environment variable holding the path of the marker file created on startup completion
+pub const STARTUP_MARKER_ENV: &str = "ECLIPSE_STARTUP_MARKER";
+
+/// Time the output of the JVM is captured after requesting a thread dump
+const THREAD_DUMP_CAPTURE_TIME: Duration = Duration::from_secs(2);
+
+const MARKER_FILE: &str = "started";
+
+/// Number of names tried for the launcher log, in case files with the same name exist
+const MAX_LOG_FILE_ATTEMPTS: u32 = 100;
+
+/// Signals the launcher that started this JVM that the startup completed.
+/// Does nothing if the launcher does not watch the startup.
+pub fn mark_startup_complete() {
+    if let Some(marker) = std::env::var_os(STARTUP_MARKER_ENV) {
+        // nothing we can do on failure, the launcher will report the timeout
+        let _ = File::create(marker);
+    }
+}
+
+/// Copies the standard output of the JVM to the standard output of the launcher,
+/// while being able to capture it into a buffer.
+#[derive(Debug)]
+struct OutputCapture {
+    capturing: Arc<AtomicBool>,
+    buffer: Arc<Mutex<Vec<u8>>>,
+}
+
+impl OutputCapture {
+    fn relay(mut output: ChildStdout) -> OutputCapture {
+        let capturing = Arc::new(AtomicBool::new(false));
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let thread_capturing = Arc::clone(&capturing);
+        let thread_buffer = Arc::clone(&buffer);
+        thread::spawn(move || {
+            let mut stdout = std::io::stdout();
+            let mut chunk = [0u8; 8192];
+            // stops when the JVM terminated and closed its output
+            while let Ok(len) = output.read(&mut chunk) {
+                if len == 0 {
+                    break;
+                }
+                let _ = stdout.write_all(&chunk[..len]);
+                let _ = stdout.flush();
+                if thread_capturing.load(Ordering::SeqCst) {
+                    if let Ok(mut buffer) = thread_buffer.lock() {
+                        buffer.extend_from_slice(&chunk[..len]);
+                    }
+                }
+            }
+        });
+        OutputCapture { capturing, buffer }
+    }
+
+    /// Returns the output of the JVM written within `duration` from now
+    fn capture(&self, duration: Duration) -> Vec<u8> {
+        self.capturing.store(true, Ordering::SeqCst);
+        thread::sleep(duration);
+        self.capturing.store(false, Ordering::SeqCst);
+        match self.buffer.lock() {
+            Ok(mut buffer) => std::mem::take(&mut *buffer),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+/// Watches the startup of a JVM process, see module documentation.
+#[derive(Debug)]
+pub struct StartupWatchdog {
+    timeout: Duration,
+    name: String,
+    ask_user: bool,
+    /// Holds the marker file, removed when the watchdog is dropped
+    marker_dir: PrivateDir,
+
+    /// The launcher log, created when the first thread dump is written
+    log: Option<(PathBuf, File)>,
+    deadline: Instant,
+    started: bool,
+    output: Option<OutputCapture>,
+}
+
+/// The outcome of `StartupWatchdog::check`
+#[derive(Debug, Eq, PartialEq)]
+pub enum WatchdogAction {
+    KeepWaiting,
+
+    /// The user chose to terminate the JVM, which did not start in time.
+    /// Holds the launcher log the thread dump was written to, if any.
+    Terminate(Option<PathBuf>),
+}
+
+impl StartupWatchdog {
+    /// Creates a watchdog for the given `timeout`. The program `name` is used as title
+    /// of the dialog asking the user to keep waiting. If `ask_user` is `false` (errors
+    /// are suppressed), the watchdog only writes thread dumps and keeps waiting.
+    /// Returns an error if the directory of the marker file cannot be created.
+    pub fn new(timeout: Duration, name: &str, ask_user: bool) -> Result<StartupWatchdog, Error> {
+        let marker_dir = PrivateDir::create("eclipse_startup_")?;
+        Ok(StartupWatchdog {
+            timeout,
+            name: name.to_string(),
+            ask_user,
+            marker_dir,
+            log: None,
+            deadline: Instant::now() + timeout,
+            started: false,
+            output: None,
+        })
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Returns the path of the marker file created on startup completion
+    fn marker(&self) -> PathBuf {
+        self.marker_dir.path().join(MARKER_FILE)
+    }
+
+    /// Configures the `command` starting the JVM to report its startup, and to
+    /// pipe its output through the launcher, so thread dumps can be captured.
+    pub fn prepare(&mut self, command: &mut Command) {
+        // a marker of a previous launch must not count for this one
+        let marker = self.marker();
+        let _ = std::fs::remove_file(&marker);
+        command
+            .env(STARTUP_MARKER_ENV, marker)
+            .stdout(Stdio::piped());
+    }
+
+    /// Starts watching the started JVM `child`
+    pub fn attach(&mut self, child: &mut Child) {
+        self.output = child.stdout.take().map(OutputCapture::relay);
+        self.deadline = Instant::now() + self.timeout;
+        self.started = false;
+    }
+
+    /// Checks whether the JVM process `pid` completed its startup in time. If not,
+    /// requests a thread dump and asks the user whether to keep waiting.
+    pub fn check(&mut self, pid: u32) -> WatchdogAction {
+        if self.started || Instant::now() < self.deadline {
+            return WatchdogAction::KeepWaiting;
+        }
+        let marker = self.marker();
+        if marker.exists() {
+            self.started = true;
+            let _ = std::fs::remove_file(marker);
+            return WatchdogAction::KeepWaiting;
+        }
+        let log_file = self.write_thread_dump(pid);
+        if self.ask_user && !self.ask_keep_waiting(log_file.as_ref()) {
+            return WatchdogAction::Terminate(log_file);
+        }
+        self.deadline = Instant::now() + self.timeout;
+        WatchdogAction::KeepWaiting
+    }
+
+    /// Requests a thread dump from the JVM process `pid` and appends it to the launcher
+    /// log. Returns the path of the launcher log, or `None` if no dump was written.
+    fn write_thread_dump(&mut self, pid: u32) -> Option<PathBuf> {
+        let output = self.output.as_ref()?;
+        if !request_thread_dump(pid) {
+            return None;
+        }
+        let dump = output.capture(THREAD_DUMP_CAPTURE_TIME);
+        if self.log.is_none() {
+            self.log = create_log_file().ok();
+        }
+        let (log_file, log) = self.log.as_mut()?;
+        let header = format!(
+            "Thread dump of JVM process {}, startup not completed within {}s:\n",
+            pid,
+            self.timeout.as_secs()
+        );
+        log.write_all(header.as_bytes()).ok()?;
+        log.write_all(&dump).ok()?;
+        Some(log_file.clone())
+    }
+
+    fn ask_keep_waiting(&self, log_file: Option<&PathBuf>) -> bool {
+        let mut msg = format!(
+            "{} did not complete its startup within {} seconds.",
+            self.name,
+            self.timeout.as_secs()
+        );
+        if let Some(log_file) = log_file {
+            msg.push_str(&format!(
+                " A thread dump was written to {}.",
+                log_file.display()
+            ));
+        }
+        msg.push_str("\n\nKeep waiting? Otherwise the Java VM is terminated.");
+        match ask_question(&msg, &self.name) {
+            Ok(keep_waiting) => keep_waiting,
+            Err(err) => {
+                // dialog failed, rather keep waiting than terminating without consent
+                eprintln!("{}\n{}", msg, err);
+                true
+            }
+        }
+    }
+}
+
+/// Creates a new launcher log in the temp folder. An existing file is never
+/// opened, since it may have been placed there by another user.
+fn create_log_file() -> Result<(PathBuf, File), Error> {
+    let temp_dir = std::env::temp_dir();
+    let launcher_pid = std::process::id();
+    for attempt in 0..MAX_LOG_FILE_ATTEMPTS {
+        let name = match attempt {
+            0 => format!("eclipse_launcher_{}.log", launcher_pid),
+            _ => format!("eclipse_launcher_{}_{}.log", launcher_pid, attempt),
+        };
+        let path = temp_dir.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Err(Error::new(
+        ErrorKind::AlreadyExists,
+        "no unused log file name found",
+    ))
+}
+
+/// Sends `SIGQUIT` to the JVM process `pid`, which makes the JVM print
+/// a thread dump to its standard output.
+#[cfg(not(target_os = "windows"))]
+fn request_thread_dump(pid: u32) -> bool {
+    // safe, kill has no memory related preconditions
+    unsafe { libc::kill(pid as libc::pid_t, libc::SIGQUIT) == 0 }
+}
+
+/// Windows has no `SIGQUIT`, thread dumps can only be requested from a console
+#[cfg(target_os = "windows")]
+fn request_thread_dump(_pid: u32) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::{create_log_file, StartupWatchdog, WatchdogAction};
+    use std::fs::File;
+    use std::time::Duration;
+
+    #[test]
+    fn test_marker_completes_startup() {
+        // every watchdog has its own marker directory
+        let mut watchdog = StartupWatchdog::new(Duration::from_secs(0), "Eclipse", false).unwrap();
+        File::create(watchdog.marker()).unwrap();
+
+        assert_eq!(watchdog.check(0), WatchdogAction::KeepWaiting);
+        assert!(watchdog.started);
+        assert!(!watchdog.marker().exists());
+    }
+
+    #[test]
+    fn test_timeout_without_user_keeps_waiting() {
+        let mut watchdog = StartupWatchdog::new(Duration::from_secs(0), "Eclipse", false).unwrap();
+
+        assert_eq!(watchdog.check(0), WatchdogAction::KeepWaiting);
+        assert!(!watchdog.started);
+    }
+
+    #[test]
+    fn test_log_file_not_reused() {
+        let (first, _) = create_log_file().unwrap();
+        let (second, _) = create_log_file().unwrap();
+        let _ = std::fs::remove_file(&first);
+        let _ = std::fs::remove_file(&second);
+        assert_ne!(first, second);
+    }
+}